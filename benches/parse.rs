@@ -0,0 +1,299 @@
+//! Parser benchmarks.
+//!
+//! To compare against an earlier revision of the parser, run
+//! `cargo bench -- --save-baseline before` on it, then `cargo bench -- --baseline before`
+//! on the revision under test.
+//!
+//! The `corpus` group runs over the glif files in the directory named by the
+//! `GLIF_CORPUS` environment variable, e.g. the `glyphs` directory of a large UFO, and
+//! compares against norad's own glif parser. It is skipped when the variable is unset.
+
+use std::path::PathBuf;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use quick_xml::{events::Event, Reader};
+use ufoglifparser::{
+    parse_glif, parse_glif_ref, parse_glif_with_options, read_glif_header, visit_glif, GlifVisitor,
+    ParseOptions, PointRef,
+};
+
+/// A glyph with a short outline and a lib the size of what hinting and editor data
+/// tends to add to real-world glyphs.
+fn lib_heavy_glif() -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<glyph name="Adieresis" format="2">
+  <advance width="600"/>
+  <unicode hex="00C4"/>
+  <anchor name="top" x="300" y="700"/>
+  <outline>
+    <component base="A"/>
+    <component base="dieresiscomb" xOffset="120"/>
+  </outline>
+  <lib>
+    <dict>
+      <key>public.postscript.hints</key>
+      <dict>
+        <key>hintSetList</key>
+        <array>
+"#,
+    );
+    for i in 0..500 {
+        xml.push_str(&format!(
+            "          <dict>\n            <key>pointTag</key>\n            \
+             <string>hintSet{:04}</string>\n            <key>stems</key>\n            \
+             <array>\n              <string>hstem {} 20</string>\n              \
+             <string>vstem {} 30</string>\n            </array>\n          </dict>\n",
+            i, i, i
+        ));
+    }
+    xml.push_str(
+        r#"        </array>
+      </dict>
+    </dict>
+  </lib>
+  <note>Built from the A and dieresiscomb glyphs.</note>
+</glyph>
+"#,
+    );
+    xml
+}
+
+/// A glyph with a long outline, to exercise the per-attribute hot path.
+fn outline_heavy_glif() -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<glyph name="ornament" format="2">
+  <advance width="1000"/>
+  <outline>
+"#,
+    );
+    for contour in 0..100 {
+        xml.push_str("    <contour>\n");
+        for point in 0..10 {
+            xml.push_str(&format!(
+                "      <point x=\"{}.5\" y=\"{}\" type=\"line\"/>\n      \
+                 <point x=\"{}\" y=\"-{}.25\"/>\n      \
+                 <point x=\"{}\" y=\"{}\"/>\n      \
+                 <point x=\"{}\" y=\"{}\" type=\"curve\" smooth=\"yes\"/>\n",
+                contour * 10 + point,
+                point * 7,
+                contour + point,
+                point,
+                point * 3,
+                contour,
+                point + 1,
+                contour * 2
+            ));
+        }
+        xml.push_str("    </contour>\n");
+    }
+    xml.push_str("  </outline>\n</glyph>\n");
+    xml
+}
+
+fn outline(c: &mut Criterion) {
+    let xml = outline_heavy_glif();
+    let mut group = c.benchmark_group("outline-heavy");
+    group.throughput(Throughput::Bytes(xml.len() as u64));
+
+    group.bench_function("parse_glif", |b| {
+        b.iter(|| parse_glif(black_box(xml.as_bytes())).unwrap())
+    });
+    group.bench_function("parse_glif_ref", |b| {
+        b.iter(|| parse_glif_ref(black_box(xml.as_bytes())).unwrap())
+    });
+
+    group.finish();
+}
+
+/// The attribute path the parser had before it parsed numbers from raw bytes: every
+/// value is unescaped and decoded, then parsed as a string. Kept here to benchmark
+/// the byte parser against; sums the point coordinates so nothing is optimized away.
+fn unescape_decode_parse(xml: &[u8]) -> f64 {
+    fn parse_number(value: &str) -> f64 {
+        assert!(is_number(value.as_bytes()));
+        let number: f64 = value.parse().unwrap();
+        assert!(number.is_finite());
+        number
+    }
+
+    // The number grammar check the parser did on the decoded string.
+    fn is_number(value: &[u8]) -> bool {
+        fn skip_digits(value: &[u8]) -> (&[u8], usize) {
+            let n = value.iter().take_while(|b| b.is_ascii_digit()).count();
+            (&value[n..], n)
+        }
+
+        let value = value.strip_prefix(b"-").unwrap_or(value);
+        let (value, integer_digits) = skip_digits(value);
+        let (value, fraction_digits) = match value.strip_prefix(b".") {
+            Some(value) => skip_digits(value),
+            None => (value, 0),
+        };
+        if integer_digits == 0 && fraction_digits == 0 {
+            return false;
+        }
+        match value {
+            [] => true,
+            [b'e' | b'E', exponent @ ..] => {
+                let exponent = match exponent {
+                    [b'+' | b'-', rest @ ..] => rest,
+                    rest => rest,
+                };
+                let (rest, exponent_digits) = skip_digits(exponent);
+                rest.is_empty() && exponent_digits > 0
+            }
+            _ => false,
+        }
+    }
+
+    let mut reader = Reader::from_reader(xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut sum = 0.0;
+    loop {
+        match reader.read_event(&mut buf).unwrap() {
+            Event::Empty(e) if e.name() == b"point" => {
+                for attr in e.attributes() {
+                    let attr = attr.unwrap();
+                    if attr.key == b"x" || attr.key == b"y" {
+                        let value = attr.unescaped_value().unwrap();
+                        let value = reader.decode(&value).unwrap();
+                        sum += parse_number(value);
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    sum
+}
+
+/// Sums the point coordinates like [`unescape_decode_parse`], through the parser.
+#[derive(Default)]
+struct CoordinateSum(f64);
+
+impl<'a> GlifVisitor<'a> for CoordinateSum {
+    fn point(&mut self, point: PointRef<'a>) -> Result<(), ufoglifparser::Error> {
+        self.0 += point.x + point.y;
+        Ok(())
+    }
+}
+
+fn attributes(c: &mut Criterion) {
+    let xml = outline_heavy_glif();
+    let mut group = c.benchmark_group("point-attributes");
+    group.throughput(Throughput::Bytes(xml.len() as u64));
+
+    group.bench_function("unescape + decode + parse", |b| {
+        b.iter(|| unescape_decode_parse(black_box(xml.as_bytes())))
+    });
+    group.bench_function("raw bytes (visit_glif)", |b| {
+        b.iter(|| {
+            let mut sum = CoordinateSum::default();
+            visit_glif(
+                black_box(xml.as_bytes()),
+                &ParseOptions::default(),
+                &mut sum,
+            )
+            .unwrap();
+            sum.0
+        })
+    });
+
+    group.finish();
+}
+
+fn corpus(c: &mut Criterion) {
+    let dir = match std::env::var_os("GLIF_CORPUS") {
+        Some(dir) => PathBuf::from(dir),
+        None => return,
+    };
+    let paths: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .expect("GLIF_CORPUS must name a directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "glif"))
+        .collect();
+    let files: Vec<Vec<u8>> = paths
+        .iter()
+        .map(|path| std::fs::read(path).unwrap())
+        .collect();
+    let total: usize = files.iter().map(Vec::len).sum();
+
+    let mut group = c.benchmark_group("corpus");
+    group.throughput(Throughput::Bytes(total as u64));
+    group.sample_size(10);
+
+    group.bench_function("parse_glif", |b| {
+        b.iter(|| {
+            for xml in &files {
+                parse_glif(black_box(xml)).unwrap();
+            }
+        })
+    });
+    group.bench_function("parse_glif_ref", |b| {
+        b.iter(|| {
+            for xml in &files {
+                parse_glif_ref(black_box(xml)).unwrap();
+            }
+        })
+    });
+    // norad only parses from disk, so include reading the files for both.
+    group.bench_function("read + parse_glif", |b| {
+        b.iter(|| {
+            for path in &paths {
+                parse_glif(&std::fs::read(path).unwrap()).unwrap();
+            }
+        })
+    });
+    group.bench_function("norad", |b| {
+        b.iter(|| {
+            for path in &paths {
+                norad::Glyph::load(black_box(path)).unwrap();
+            }
+        })
+    });
+
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    let xml = lib_heavy_glif();
+    let mut group = c.benchmark_group("lib-heavy");
+
+    group.bench_function("full", |b| {
+        b.iter(|| parse_glif(black_box(xml.as_bytes())).unwrap())
+    });
+
+    let skip_lib = ParseOptions {
+        skip_lib: true,
+        ..Default::default()
+    };
+    group.bench_function("skip lib", |b| {
+        b.iter(|| parse_glif_with_options(black_box(xml.as_bytes()), &skip_lib).unwrap())
+    });
+
+    let cmap_only = ParseOptions {
+        skip_lib: true,
+        skip_note: true,
+        skip_image: true,
+        skip_anchors_and_guidelines: true,
+        skip_outline: true,
+        ..Default::default()
+    };
+    group.bench_function("cmap only", |b| {
+        b.iter(|| parse_glif_with_options(black_box(xml.as_bytes()), &cmap_only).unwrap())
+    });
+
+    group.bench_function("header", |b| {
+        b.iter(|| read_glif_header(black_box(xml.as_bytes())).unwrap())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, parse, outline, attributes, corpus);
+criterion_main!(benches);
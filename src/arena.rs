@@ -0,0 +1,442 @@
+//! Arena-backed parsing for loading whole layers.
+//!
+//! Parsing a layer into [`Glyph`]s allocates a vector for every contour and a string
+//! for every name. [`parse_glif_in`] instead puts the points, contours and names of a
+//! glyph into a [`Bump`] arena, which can be shared by all glyphs of a layer and is
+//! freed in one go. Strings are copied into the arena straight from the input.
+//! [`ArenaGlyph::to_owned`] converts into the regular glyph model.
+//!
+//! Anchors, guidelines, the image and the lib are few per glyph and stay regular
+//! [`norad`] types.
+//!
+//! Not everything is arena-backed yet: the parser still hands identifiers over as
+//! Arc-backed [`Identifier`]s before they are copied into the arena, and every call
+//! allocates the set of identifiers seen so far and a fresh read buffer.
+
+use std::borrow::Cow;
+
+use bumpalo::{collections::Vec as BumpVec, Bump};
+use norad::{
+    AffineTransform, Anchor, Component, Contour, ContourPoint, GlifVersion, Glyph, Guideline,
+    Identifier, Image, Plist, PointType,
+};
+
+use crate::builder::{OutlineError, PointSequence};
+use crate::{
+    visit_glif, AnchorRef, ComponentRef, Error, ErrorKind, GlifVisitor, GuidelineRef, ImageRef,
+    ParseOptions, PointRef, RawLib, Warning,
+};
+
+/// A glyph whose outline and names live in an arena.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArenaGlyph<'bump> {
+    pub name: &'bump str,
+    pub format: GlifVersion,
+    pub format_minor: u32,
+    pub width: f64,
+    pub height: f64,
+    pub codepoints: &'bump [char],
+    pub note: Option<&'bump str>,
+    pub guidelines: Vec<Guideline>,
+    pub anchors: Vec<Anchor>,
+    pub contours: &'bump [ArenaContour<'bump>],
+    pub components: &'bump [ArenaComponent<'bump>],
+    pub image: Option<Image>,
+    pub lib: Plist,
+}
+
+/// A contour in an arena.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArenaContour<'bump> {
+    pub points: &'bump [ArenaPoint<'bump>],
+    pub identifier: Option<&'bump str>,
+}
+
+/// A point in an arena.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArenaPoint<'bump> {
+    pub x: f64,
+    pub y: f64,
+    pub typ: PointType,
+    pub smooth: bool,
+    pub name: Option<&'bump str>,
+    pub identifier: Option<&'bump str>,
+}
+
+/// A component in an arena.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArenaComponent<'bump> {
+    pub base: &'bump str,
+    pub transform: AffineTransform,
+    pub identifier: Option<&'bump str>,
+}
+
+impl ArenaGlyph<'_> {
+    /// Convert into the regular, owned glyph model.
+    pub fn to_owned(&self) -> Glyph {
+        let mut glyph = Glyph::new_named(self.name);
+        glyph.format = self.format;
+        glyph.format_minor = self.format_minor;
+        glyph.width = self.width;
+        glyph.height = self.height;
+        glyph.codepoints = self.codepoints.to_vec();
+        glyph.note = self.note.map(String::from);
+        glyph.guidelines = self.guidelines.clone();
+        glyph.anchors = self.anchors.clone();
+        glyph.contours = self.contours.iter().map(ArenaContour::to_owned).collect();
+        glyph.components = self
+            .components
+            .iter()
+            .map(ArenaComponent::to_owned)
+            .collect();
+        glyph.image = self.image.clone();
+        glyph.lib = self.lib.clone();
+        glyph
+    }
+}
+
+impl ArenaContour<'_> {
+    pub fn to_owned(&self) -> Contour {
+        let points = self.points.iter().map(ArenaPoint::to_owned).collect();
+        Contour::new(points, self.identifier.map(identifier), None)
+    }
+}
+
+impl ArenaPoint<'_> {
+    pub fn to_owned(&self) -> ContourPoint {
+        ContourPoint::new(
+            self.x,
+            self.y,
+            self.typ,
+            self.smooth,
+            self.name.map(String::from),
+            self.identifier.map(identifier),
+            None,
+        )
+    }
+}
+
+impl ArenaComponent<'_> {
+    pub fn to_owned(&self) -> Component {
+        Component::new(
+            self.base.into(),
+            self.transform,
+            self.identifier.map(identifier),
+            None,
+        )
+    }
+}
+
+fn outline_error(e: OutlineError) -> Error {
+    Error::Parse(ErrorKind::Outline(e))
+}
+
+/// Turn an identifier validated by the parser back into an [`Identifier`].
+fn identifier(value: &str) -> Identifier {
+    Identifier::new(value).expect("identifier validated when parsing")
+}
+
+/// Parse a glif file into `bump`.
+///
+/// Use the same arena for all glyphs of a layer and drop it once done with them:
+///
+/// ```no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let bump = bumpalo::Bump::new();
+/// let mut glyphs = Vec::new();
+/// for entry in std::fs::read_dir("Font.ufo/glyphs")? {
+///     let path = entry?.path();
+///     if path.extension().is_some_and(|ext| ext == "glif") {
+///         glyphs.push(ufoglifparser::parse_glif_in(&std::fs::read(path)?, &bump)?);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_glif_in<'bump>(xml: &[u8], bump: &'bump Bump) -> Result<ArenaGlyph<'bump>, Error> {
+    parse_glif_in_with_options(xml, &ParseOptions::default(), bump).map(|(glyph, _)| glyph)
+}
+
+/// Parse a glif file into `bump` according to the given [`ParseOptions`].
+///
+/// The warnings are the same as for [`crate::parse_glif_with_options`].
+pub fn parse_glif_in_with_options<'bump>(
+    xml: &[u8],
+    options: &ParseOptions,
+    bump: &'bump Bump,
+) -> Result<(ArenaGlyph<'bump>, Vec<Warning>), Error> {
+    let mut collector = ArenaCollector::new(bump);
+    let mut warnings = visit_glif(xml, options, &mut collector)?;
+    let (glyph, outline_warnings) = collector.finish()?;
+    warnings.extend(outline_warnings);
+    Ok((glyph, warnings))
+}
+
+/// The visitor behind [`parse_glif_in`].
+struct ArenaCollector<'bump> {
+    bump: &'bump Bump,
+    glyph: Option<ArenaGlyph<'bump>>,
+    codepoints: BumpVec<'bump, char>,
+    contours: BumpVec<'bump, ArenaContour<'bump>>,
+    components: BumpVec<'bump, ArenaComponent<'bump>>,
+    sequence: PointSequence,
+    /// The identifier and points of the contour being read.
+    contour: Option<(Option<&'bump str>, BumpVec<'bump, ArenaPoint<'bump>>)>,
+    warnings: Vec<Warning>,
+}
+
+impl<'bump> ArenaCollector<'bump> {
+    fn new(bump: &'bump Bump) -> Self {
+        ArenaCollector {
+            bump,
+            glyph: None,
+            codepoints: BumpVec::new_in(bump),
+            contours: BumpVec::new_in(bump),
+            components: BumpVec::new_in(bump),
+            sequence: PointSequence::default(),
+            contour: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn glyph_mut(&mut self) -> &mut ArenaGlyph<'bump> {
+        // The parser always reports the <glyph> element before anything else.
+        self.glyph.as_mut().expect("glyph element not visited")
+    }
+
+    fn alloc_identifier(&self, identifier: Option<Identifier>) -> Option<&'bump str> {
+        identifier.map(|identifier| &*self.bump.alloc_str(identifier.as_str()))
+    }
+
+    fn finish(self) -> Result<(ArenaGlyph<'bump>, Vec<Warning>), Error> {
+        self.sequence.finish().map_err(outline_error)?;
+        let mut glyph = self.glyph.expect("glyph element not visited");
+        glyph.codepoints = self.codepoints.into_bump_slice();
+        glyph.contours = self.contours.into_bump_slice();
+        glyph.components = self.components.into_bump_slice();
+        Ok((glyph, self.warnings))
+    }
+}
+
+impl<'a, 'bump> GlifVisitor<'a> for ArenaCollector<'bump> {
+    fn glyph(
+        &mut self,
+        name: Cow<'a, str>,
+        format: GlifVersion,
+        format_minor: u32,
+    ) -> Result<(), Error> {
+        self.glyph = Some(ArenaGlyph {
+            name: self.bump.alloc_str(&name),
+            format,
+            format_minor,
+            width: 0.0,
+            height: 0.0,
+            codepoints: &[],
+            note: None,
+            guidelines: Vec::new(),
+            anchors: Vec::new(),
+            contours: &[],
+            components: &[],
+            image: None,
+            lib: Plist::new(),
+        });
+        Ok(())
+    }
+
+    fn advance(&mut self, width: f64, height: f64) -> Result<(), Error> {
+        let glyph = self.glyph_mut();
+        glyph.width = width;
+        glyph.height = height;
+        Ok(())
+    }
+
+    fn unicode(&mut self, codepoint: char) -> Result<(), Error> {
+        self.codepoints.push(codepoint);
+        Ok(())
+    }
+
+    fn anchor(&mut self, anchor: AnchorRef<'a>) -> Result<(), Error> {
+        self.glyph_mut().anchors.push(anchor.to_owned());
+        Ok(())
+    }
+
+    fn guideline(&mut self, guideline: GuidelineRef<'a>) -> Result<(), Error> {
+        self.glyph_mut().guidelines.push(guideline.to_owned());
+        Ok(())
+    }
+
+    fn image(&mut self, image: ImageRef<'a>) -> Result<(), Error> {
+        self.glyph_mut().image = Some(image.to_owned());
+        Ok(())
+    }
+
+    fn begin_contour(&mut self, identifier: Option<Identifier>) -> Result<(), Error> {
+        self.sequence.begin_path().map_err(outline_error)?;
+        let identifier = self.alloc_identifier(identifier);
+        self.contour = Some((identifier, BumpVec::new_in(self.bump)));
+        Ok(())
+    }
+
+    fn point(&mut self, point: PointRef<'a>) -> Result<(), Error> {
+        self.sequence
+            .add_point((point.x, point.y), point.typ, point.smooth)
+            .map_err(outline_error)?;
+        let name = point.name.map(|name| &*self.bump.alloc_str(&name));
+        let identifier = self.alloc_identifier(point.identifier);
+        // The sequence only accepts points while a contour is being read.
+        let (_, points) = self.contour.as_mut().unwrap();
+        points.push(ArenaPoint {
+            x: point.x,
+            y: point.y,
+            typ: point.typ,
+            smooth: point.smooth,
+            name,
+            identifier,
+        });
+        Ok(())
+    }
+
+    fn end_contour(&mut self) -> Result<(), Error> {
+        let contour = self.contour.take();
+        let points = contour.as_ref().map_or(&[][..], |(_, points)| &points[..]);
+        let index = self
+            .sequence
+            .end_path(|i| ((points[i].x, points[i].y), points[i].typ))
+            .map_err(outline_error)?;
+        let (id, points) = contour.unwrap();
+        // Like the outline builder, skip empty contours but note them.
+        if points.is_empty() {
+            self.warnings.push(Warning::EmptyContour {
+                contour: index,
+                identifier: id.map(identifier),
+            });
+        } else {
+            self.contours.push(ArenaContour {
+                points: points.into_bump_slice(),
+                identifier: id,
+            });
+        }
+        Ok(())
+    }
+
+    fn component(&mut self, component: ComponentRef<'a>) -> Result<(), Error> {
+        let component = ArenaComponent {
+            base: self.bump.alloc_str(&component.base),
+            transform: component.transform,
+            identifier: self.alloc_identifier(component.identifier),
+        };
+        self.components.push(component);
+        Ok(())
+    }
+
+    fn lib(&mut self, lib: RawLib<'a>) -> Result<(), Error> {
+        self.glyph_mut().lib = lib.to_plist()?;
+        Ok(())
+    }
+
+    fn note(&mut self, note: Cow<'a, str>) -> Result<(), Error> {
+        let note = self.bump.alloc_str(&note);
+        self.glyph_mut().note = Some(note);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const GLIF: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<glyph name="Aacute" format="2">
+  <advance width="600"/>
+  <unicode hex="00C1"/>
+  <anchor name="top" x="300" y="700"/>
+  <outline>
+    <contour identifier="contour1">
+      <point x="0" y="0" type="line" name="corner"/>
+      <point x="600" y="0" type="line"/>
+      <point x="300" y="700" type="line" identifier="apex"/>
+    </contour>
+    <contour/>
+    <component base="acutecomb" xOffset="120"/>
+  </outline>
+  <note>From A.</note>
+</glyph>
+"#;
+
+    #[test]
+    fn parse_into_arena() {
+        let bump = Bump::new();
+        let glyph = parse_glif_in(GLIF.as_bytes(), &bump).unwrap();
+
+        assert_eq!(glyph.name, "Aacute");
+        assert_eq!(glyph.codepoints, &['Á']);
+        assert_eq!(glyph.note, Some("From A."));
+        assert_eq!(glyph.contours.len(), 1);
+        assert_eq!(glyph.contours[0].identifier, Some("contour1"));
+        assert_eq!(glyph.contours[0].points[0].name, Some("corner"));
+        assert_eq!(glyph.contours[0].points[2].identifier, Some("apex"));
+        assert_eq!(glyph.components[0].base, "acutecomb");
+        assert!(bump.allocated_bytes() > 0);
+
+        assert_eq!(
+            glyph.to_owned(),
+            crate::parse_glif(GLIF.as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn shared_arena() {
+        let bump = Bump::new();
+        let first = parse_glif_in(GLIF.as_bytes(), &bump).unwrap();
+        let other = GLIF.replace("Aacute", "Agrave").replace("00C1", "00C0");
+        let second = parse_glif_in(other.as_bytes(), &bump).unwrap();
+
+        assert_eq!(first.name, "Aacute");
+        assert_eq!(second.name, "Agrave");
+        assert_eq!(first.contours, second.contours);
+    }
+
+    #[test]
+    fn bad_point_sequence() {
+        let bump = Bump::new();
+        let xml = GLIF.replace(r#"type="line" name="corner""#, r#"name="corner""#);
+        assert!(matches!(
+            parse_glif_in(xml.as_bytes(), &bump),
+            Err(Error::Parse(ErrorKind::Outline(_)))
+        ));
+    }
+
+    #[test]
+    fn options_and_warnings() {
+        let bump = Bump::new();
+        let xml = GLIF.replace("<contour/>", r#"<contour identifier="empty"/>"#);
+        let options = ParseOptions {
+            skip_note: true,
+            ..Default::default()
+        };
+        let (glyph, warnings) =
+            parse_glif_in_with_options(xml.as_bytes(), &options, &bump).unwrap();
+        assert_eq!(glyph.note, None);
+        assert_eq!(
+            warnings,
+            vec![Warning::EmptyContour {
+                contour: 1,
+                identifier: Some(Identifier::new("empty").unwrap()),
+            }]
+        );
+
+        let options = ParseOptions {
+            limits: crate::Limits {
+                max_points: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(matches!(
+            parse_glif_in_with_options(xml.as_bytes(), &options, &bump),
+            Err(Error::Parse(ErrorKind::LimitExceeded { .. }))
+        ));
+    }
+}
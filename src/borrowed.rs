@@ -0,0 +1,790 @@
+//! A zero-copy representation of a parsed glyph.
+//!
+//! [`parse_glif_ref`] returns a [`GlyphRef`] whose names, note and lib strings
+//! borrow from the input buffer whenever they contain no escapes, which makes
+//! read-only analysis of huge fonts considerably cheaper than building a [`Glyph`]
+//! for every glif file. [`GlyphRef::to_owned`] converts into the regular glyph model.
+//!
+//! The borrowed types are also what [`crate::visit_glif`] hands to a
+//! [`GlifVisitor`], and [`parse_glif_ref`] is just the visitor that collects them.
+
+use std::borrow::Cow;
+
+use norad::{
+    AffineTransform, Anchor, Color, Component, Contour, ContourPoint, GlifVersion, Glyph,
+    Guideline, Identifier, Image, Line, Plist, PointType,
+};
+use quick_xml::{events::Event, Reader};
+
+use crate::builder::{OutlineError, PointSequence};
+use crate::{read_text, visit_glif, Error, ErrorKind, GlifVisitor, Limits, ParseOptions, Warning};
+
+/// A glyph borrowing its strings from the glif file it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphRef<'a> {
+    pub name: Cow<'a, str>,
+    pub format: GlifVersion,
+    pub format_minor: u32,
+    pub width: f64,
+    pub height: f64,
+    pub codepoints: Vec<char>,
+    pub note: Option<Cow<'a, str>>,
+    pub guidelines: Vec<GuidelineRef<'a>>,
+    pub anchors: Vec<AnchorRef<'a>>,
+    pub contours: Vec<ContourRef<'a>>,
+    pub components: Vec<ComponentRef<'a>>,
+    pub image: Option<ImageRef<'a>>,
+    pub lib: DictionaryRef<'a>,
+}
+
+/// A borrowed [`Anchor`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnchorRef<'a> {
+    pub x: f64,
+    pub y: f64,
+    pub name: Option<Cow<'a, str>>,
+    pub color: Option<Color>,
+    pub identifier: Option<Identifier>,
+}
+
+/// A borrowed [`Guideline`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuidelineRef<'a> {
+    pub line: Line,
+    pub name: Option<Cow<'a, str>>,
+    pub color: Option<Color>,
+    pub identifier: Option<Identifier>,
+}
+
+/// A borrowed [`norad::Contour`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContourRef<'a> {
+    pub points: Vec<PointRef<'a>>,
+    pub identifier: Option<Identifier>,
+}
+
+/// A borrowed [`norad::ContourPoint`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointRef<'a> {
+    pub x: f64,
+    pub y: f64,
+    pub typ: PointType,
+    pub smooth: bool,
+    pub name: Option<Cow<'a, str>>,
+    pub identifier: Option<Identifier>,
+}
+
+/// A borrowed [`norad::Component`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentRef<'a> {
+    pub base: Cow<'a, str>,
+    pub transform: AffineTransform,
+    pub identifier: Option<Identifier>,
+}
+
+/// A borrowed [`Image`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageRef<'a> {
+    pub file_name: Cow<'a, str>,
+    pub color: Option<Color>,
+    pub transform: AffineTransform,
+}
+
+/// A borrowed plist dictionary, with its entries in document order.
+pub type DictionaryRef<'a> = Vec<(Cow<'a, str>, ValueRef<'a>)>;
+
+/// A borrowed [`plist::Value`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueRef<'a> {
+    Dictionary(DictionaryRef<'a>),
+    Array(Vec<ValueRef<'a>>),
+    String(Cow<'a, str>),
+    Integer(plist::Integer),
+    Real(f64),
+    Boolean(bool),
+    Date(plist::Date),
+    Data(Vec<u8>),
+}
+
+impl GlyphRef<'_> {
+    /// Convert into the regular, owned glyph model.
+    pub fn to_owned(&self) -> Glyph {
+        let mut glyph = Glyph::new_named(&*self.name);
+        glyph.format = self.format;
+        glyph.format_minor = self.format_minor;
+        glyph.width = self.width;
+        glyph.height = self.height;
+        glyph.codepoints = self.codepoints.clone();
+        glyph.note = self.note.as_ref().map(|note| note.to_string());
+        glyph.guidelines = self.guidelines.iter().map(GuidelineRef::to_owned).collect();
+        glyph.anchors = self.anchors.iter().map(AnchorRef::to_owned).collect();
+        glyph.contours = self.contours.iter().map(ContourRef::to_owned).collect();
+        glyph.components = self.components.iter().map(ComponentRef::to_owned).collect();
+        glyph.image = self.image.as_ref().map(ImageRef::to_owned);
+        glyph.lib = dictionary_to_owned(&self.lib);
+        glyph
+    }
+}
+
+impl AnchorRef<'_> {
+    pub fn to_owned(&self) -> Anchor {
+        Anchor::new(
+            self.x,
+            self.y,
+            self.name.as_ref().map(|name| name.to_string()),
+            self.color,
+            self.identifier.clone(),
+            None,
+        )
+    }
+}
+
+impl GuidelineRef<'_> {
+    pub fn to_owned(&self) -> Guideline {
+        Guideline::new(
+            self.line.clone(),
+            self.name.as_ref().map(|name| name.to_string()),
+            self.color,
+            self.identifier.clone(),
+            None,
+        )
+    }
+}
+
+impl ContourRef<'_> {
+    pub fn to_owned(&self) -> Contour {
+        let points = self.points.iter().map(PointRef::to_owned).collect();
+        Contour::new(points, self.identifier.clone(), None)
+    }
+}
+
+impl PointRef<'_> {
+    pub fn to_owned(&self) -> ContourPoint {
+        ContourPoint::new(
+            self.x,
+            self.y,
+            self.typ,
+            self.smooth,
+            self.name.as_ref().map(|name| name.to_string()),
+            self.identifier.clone(),
+            None,
+        )
+    }
+}
+
+impl ComponentRef<'_> {
+    pub fn to_owned(&self) -> Component {
+        Component::new(
+            (*self.base).into(),
+            self.transform,
+            self.identifier.clone(),
+            None,
+        )
+    }
+}
+
+impl ImageRef<'_> {
+    pub fn to_owned(&self) -> Image {
+        Image {
+            file_name: self.file_name.as_ref().into(),
+            color: self.color,
+            transform: self.transform,
+        }
+    }
+}
+
+impl ValueRef<'_> {
+    pub fn to_owned(&self) -> plist::Value {
+        match self {
+            ValueRef::Dictionary(dict) => plist::Value::Dictionary(dictionary_to_owned(dict)),
+            ValueRef::Array(array) => {
+                plist::Value::Array(array.iter().map(ValueRef::to_owned).collect())
+            }
+            ValueRef::String(string) => plist::Value::String(string.to_string()),
+            ValueRef::Integer(integer) => plist::Value::Integer(*integer),
+            ValueRef::Real(real) => plist::Value::Real(*real),
+            ValueRef::Boolean(boolean) => plist::Value::Boolean(*boolean),
+            ValueRef::Date(date) => plist::Value::Date(*date),
+            ValueRef::Data(data) => plist::Value::Data(data.clone()),
+        }
+    }
+}
+
+fn dictionary_to_owned(dict: &DictionaryRef) -> Plist {
+    dict.iter()
+        .map(|(key, value)| (key.to_string(), value.to_owned()))
+        .collect()
+}
+
+/// The content of a `<lib>` element, as handed to [`GlifVisitor::lib`].
+///
+/// The parser has checked the nesting depth, string lengths and entities against the
+/// [`Limits`], but not the plist structure; that happens on conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawLib<'a> {
+    xml: &'a [u8],
+    max_string_length: usize,
+}
+
+impl<'a> RawLib<'a> {
+    pub(crate) fn new(xml: &'a [u8], limits: &Limits) -> Self {
+        RawLib {
+            xml,
+            max_string_length: limits.max_string_length,
+        }
+    }
+
+    /// The XML between the `<lib>` and `</lib>` tags.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.xml
+    }
+
+    /// Parse into a regular plist dictionary.
+    pub fn to_plist(&self) -> Result<Plist, Error> {
+        plist::Value::from_reader_xml(self.xml)
+            .map_err(|source| Error::Parse(ErrorKind::ParsePlist(source.into())))?
+            .into_dictionary()
+            .ok_or(Error::Parse(ErrorKind::LibMustBeDictionary))
+    }
+
+    /// Parse into a dictionary borrowing its keys and strings from the input.
+    pub fn to_dictionary_ref(&self) -> Result<DictionaryRef<'a>, Error> {
+        let mut reader = Reader::from_reader(self.xml);
+        let mut buf = Vec::new();
+        let lib = match next_plist_item(&mut reader, &mut buf)? {
+            PlistItem::Element {
+                tag: PlistTag::Dict,
+                empty,
+                start,
+            } => match self.parse_value(&mut reader, &mut buf, PlistTag::Dict, empty, start)? {
+                ValueRef::Dictionary(dict) => dict,
+                _ => unreachable!(),
+            },
+            _ => return Err(Error::Parse(ErrorKind::LibMustBeDictionary)),
+        };
+        if !matches!(next_plist_item(&mut reader, &mut buf)?, PlistItem::Eof) {
+            return Err(Error::Parse(ErrorKind::LibMustBeDictionary));
+        }
+        Ok(lib)
+    }
+
+    /// Parse the value of the element `tag` just read, which started at `start`.
+    ///
+    /// The parser has limited the nesting depth already, so recursing is fine.
+    fn parse_value(
+        &self,
+        reader: &mut Reader<&[u8]>,
+        buf: &mut Vec<u8>,
+        tag: PlistTag,
+        empty: bool,
+        start: usize,
+    ) -> Result<ValueRef<'a>, Error> {
+        let text = |reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>| {
+            if empty {
+                Ok(Cow::Borrowed(""))
+            } else {
+                read_text(
+                    reader,
+                    buf,
+                    self.xml,
+                    tag.name(),
+                    self.max_string_length,
+                    || lib_error("expected text"),
+                )
+            }
+        };
+        let value = match tag {
+            PlistTag::Dict => {
+                let mut dict = Vec::new();
+                if !empty {
+                    loop {
+                        let key = match next_plist_item(reader, buf)? {
+                            PlistItem::Element {
+                                tag: PlistTag::Key,
+                                empty,
+                                start,
+                            } => {
+                                match self.parse_value(reader, buf, PlistTag::Key, empty, start)? {
+                                    ValueRef::String(key) => key,
+                                    _ => unreachable!(),
+                                }
+                            }
+                            PlistItem::End => break,
+                            _ => return Err(lib_error("expected a key")),
+                        };
+                        let value = match next_plist_item(reader, buf)? {
+                            PlistItem::Element { tag, empty, start } if tag != PlistTag::Key => {
+                                self.parse_value(reader, buf, tag, empty, start)?
+                            }
+                            _ => return Err(lib_error("expected a value")),
+                        };
+                        dict.push((key, value));
+                    }
+                }
+                ValueRef::Dictionary(dict)
+            }
+            PlistTag::Array => {
+                let mut array = Vec::new();
+                if !empty {
+                    loop {
+                        match next_plist_item(reader, buf)? {
+                            PlistItem::Element { tag, empty, start } if tag != PlistTag::Key => {
+                                array.push(self.parse_value(reader, buf, tag, empty, start)?)
+                            }
+                            PlistItem::End => break,
+                            _ => return Err(lib_error("expected a value")),
+                        }
+                    }
+                }
+                ValueRef::Array(array)
+            }
+            PlistTag::Key | PlistTag::String => ValueRef::String(text(reader, buf)?),
+            PlistTag::Integer => {
+                let text = text(reader, buf)?;
+                let text = text.trim();
+                match text.parse::<i64>() {
+                    Ok(integer) => ValueRef::Integer(integer.into()),
+                    Err(_) => ValueRef::Integer(
+                        text.parse::<u64>()
+                            .map_err(|_| lib_error("invalid integer"))?
+                            .into(),
+                    ),
+                }
+            }
+            PlistTag::Real => ValueRef::Real(
+                text(reader, buf)?
+                    .trim()
+                    .parse()
+                    .map_err(|_| lib_error("invalid real number"))?,
+            ),
+            PlistTag::True | PlistTag::False => {
+                if !empty && !text(reader, buf)?.is_empty() {
+                    return Err(lib_error("booleans must be empty"));
+                }
+                ValueRef::Boolean(tag == PlistTag::True)
+            }
+            // Dates and data are rare in glyph libs; let the plist crate decode the
+            // element as it stands in the input.
+            PlistTag::Date | PlistTag::Data => {
+                text(reader, buf)?;
+                let element = &self.xml[start..reader.buffer_position()];
+                match plist::Value::from_reader_xml(element)
+                    .map_err(|source| Error::Parse(ErrorKind::ParsePlist(source.into())))?
+                {
+                    plist::Value::Date(date) => ValueRef::Date(date),
+                    plist::Value::Data(data) => ValueRef::Data(data),
+                    _ => unreachable!(),
+                }
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// The plist elements, copied out of the reader's buffer so that nested values can
+/// be read with it.
+#[derive(Clone, Copy, PartialEq)]
+enum PlistTag {
+    Dict,
+    Array,
+    Key,
+    String,
+    Integer,
+    Real,
+    True,
+    False,
+    Date,
+    Data,
+}
+
+impl PlistTag {
+    fn from_name(name: &[u8]) -> Result<Self, Error> {
+        Ok(match name {
+            b"dict" => PlistTag::Dict,
+            b"array" => PlistTag::Array,
+            b"key" => PlistTag::Key,
+            b"string" => PlistTag::String,
+            b"integer" => PlistTag::Integer,
+            b"real" => PlistTag::Real,
+            b"true" => PlistTag::True,
+            b"false" => PlistTag::False,
+            b"date" => PlistTag::Date,
+            b"data" => PlistTag::Data,
+            _ => return Err(lib_error("unknown plist element")),
+        })
+    }
+
+    fn name(self) -> &'static [u8] {
+        match self {
+            PlistTag::Dict => b"dict",
+            PlistTag::Array => b"array",
+            PlistTag::Key => b"key",
+            PlistTag::String => b"string",
+            PlistTag::Integer => b"integer",
+            PlistTag::Real => b"real",
+            PlistTag::True => b"true",
+            PlistTag::False => b"false",
+            PlistTag::Date => b"date",
+            PlistTag::Data => b"data",
+        }
+    }
+}
+
+fn lib_error(message: &'static str) -> Error {
+    Error::Parse(ErrorKind::ParsePlist(message.into()))
+}
+
+/// What comes next inside a plist container.
+enum PlistItem {
+    /// An element, with the position of its `<` in the input.
+    Element {
+        tag: PlistTag,
+        empty: bool,
+        start: usize,
+    },
+    End,
+    Eof,
+}
+
+/// Read the next element or closing tag, skipping comments and indentation.
+fn next_plist_item(reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Result<PlistItem, Error> {
+    loop {
+        let item = match reader.read_event(buf).map_err(Error::Xml)? {
+            // The reader is just past the `>`, or the `/>` of an empty element.
+            Event::Start(e) => PlistItem::Element {
+                tag: PlistTag::from_name(e.name())?,
+                empty: false,
+                start: reader.buffer_position() - e.len() - 2,
+            },
+            Event::Empty(e) => PlistItem::Element {
+                tag: PlistTag::from_name(e.name())?,
+                empty: true,
+                start: reader.buffer_position() - e.len() - 3,
+            },
+            Event::End(_) => PlistItem::End,
+            Event::Eof => PlistItem::Eof,
+            Event::Text(e) if e.iter().all(u8::is_ascii_whitespace) => {
+                buf.clear();
+                continue;
+            }
+            Event::Comment(_) => {
+                buf.clear();
+                continue;
+            }
+            _ => return Err(lib_error("unexpected content")),
+        };
+        buf.clear();
+        return Ok(item);
+    }
+}
+
+/// Parse a glif file into a [`GlyphRef`] borrowing from `xml`, treating every problem
+/// as an error.
+///
+/// Validation is the same as for [`crate::parse_glif`].
+pub fn parse_glif_ref(xml: &[u8]) -> Result<GlyphRef<'_>, Error> {
+    parse_glif_ref_with_options(xml, &ParseOptions::default()).map(|(glyph, _)| glyph)
+}
+
+/// Parse a glif file into a [`GlyphRef`] borrowing from `xml`, according to the given
+/// [`ParseOptions`].
+///
+/// The warnings are the same as for [`crate::parse_glif_with_options`].
+pub fn parse_glif_ref_with_options<'a>(
+    xml: &'a [u8],
+    options: &ParseOptions,
+) -> Result<(GlyphRef<'a>, Vec<Warning>), Error> {
+    let mut collector = RefCollector::default();
+    let mut warnings = visit_glif(xml, options, &mut collector)?;
+    let (glyph, outline_warnings) = collector.finish()?;
+    warnings.extend(outline_warnings);
+    Ok((glyph, warnings))
+}
+
+/// The visitor behind [`parse_glif_ref`].
+#[derive(Debug, Default)]
+struct RefCollector<'a> {
+    glyph: Option<GlyphRef<'a>>,
+    sequence: PointSequence,
+    /// The contour being read.
+    contour: Option<ContourRef<'a>>,
+    warnings: Vec<Warning>,
+}
+
+impl<'a> RefCollector<'a> {
+    fn glyph_mut(&mut self) -> &mut GlyphRef<'a> {
+        // The parser always reports the <glyph> element before anything else.
+        self.glyph.as_mut().expect("glyph element not visited")
+    }
+
+    fn finish(self) -> Result<(GlyphRef<'a>, Vec<Warning>), Error> {
+        self.sequence.finish().map_err(outline_error)?;
+        let glyph = self.glyph.expect("glyph element not visited");
+        Ok((glyph, self.warnings))
+    }
+}
+
+fn outline_error(e: OutlineError) -> Error {
+    Error::Parse(ErrorKind::Outline(e))
+}
+
+impl<'a> GlifVisitor<'a> for RefCollector<'a> {
+    fn glyph(
+        &mut self,
+        name: Cow<'a, str>,
+        format: GlifVersion,
+        format_minor: u32,
+    ) -> Result<(), Error> {
+        self.glyph = Some(GlyphRef {
+            name,
+            format,
+            format_minor,
+            width: 0.0,
+            height: 0.0,
+            codepoints: Vec::new(),
+            note: None,
+            guidelines: Vec::new(),
+            anchors: Vec::new(),
+            contours: Vec::new(),
+            components: Vec::new(),
+            image: None,
+            lib: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn advance(&mut self, width: f64, height: f64) -> Result<(), Error> {
+        let glyph = self.glyph_mut();
+        glyph.width = width;
+        glyph.height = height;
+        Ok(())
+    }
+
+    fn unicode(&mut self, codepoint: char) -> Result<(), Error> {
+        self.glyph_mut().codepoints.push(codepoint);
+        Ok(())
+    }
+
+    fn anchor(&mut self, anchor: AnchorRef<'a>) -> Result<(), Error> {
+        self.glyph_mut().anchors.push(anchor);
+        Ok(())
+    }
+
+    fn guideline(&mut self, guideline: GuidelineRef<'a>) -> Result<(), Error> {
+        self.glyph_mut().guidelines.push(guideline);
+        Ok(())
+    }
+
+    fn image(&mut self, image: ImageRef<'a>) -> Result<(), Error> {
+        self.glyph_mut().image = Some(image);
+        Ok(())
+    }
+
+    fn begin_contour(&mut self, identifier: Option<Identifier>) -> Result<(), Error> {
+        self.sequence.begin_path().map_err(outline_error)?;
+        self.contour = Some(ContourRef {
+            points: Vec::new(),
+            identifier,
+        });
+        Ok(())
+    }
+
+    fn point(&mut self, point: PointRef<'a>) -> Result<(), Error> {
+        self.sequence
+            .add_point((point.x, point.y), point.typ, point.smooth)
+            .map_err(outline_error)?;
+        // The sequence only accepts points while a contour is being read.
+        self.contour.as_mut().unwrap().points.push(point);
+        Ok(())
+    }
+
+    fn end_contour(&mut self) -> Result<(), Error> {
+        let contour = self.contour.take();
+        let points = contour.as_ref().map_or(&[][..], |c| &c.points[..]);
+        let index = self
+            .sequence
+            .end_path(|i| ((points[i].x, points[i].y), points[i].typ))
+            .map_err(outline_error)?;
+        let contour = contour.unwrap();
+        // Like the outline builder, skip empty contours but note them.
+        if contour.points.is_empty() {
+            self.warnings.push(Warning::EmptyContour {
+                contour: index,
+                identifier: contour.identifier,
+            });
+        } else {
+            self.glyph_mut().contours.push(contour);
+        }
+        Ok(())
+    }
+
+    fn component(&mut self, component: ComponentRef<'a>) -> Result<(), Error> {
+        self.glyph_mut().components.push(component);
+        Ok(())
+    }
+
+    fn lib(&mut self, lib: RawLib<'a>) -> Result<(), Error> {
+        self.glyph_mut().lib = lib.to_dictionary_ref()?;
+        Ok(())
+    }
+
+    fn note(&mut self, note: Cow<'a, str>) -> Result<(), Error> {
+        self.glyph_mut().note = Some(note);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const GLIF: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<glyph name="A&amp;B" format="2">
+  <advance width="600"/>
+  <unicode hex="0041"/>
+  <anchor name="top" x="300" y="700" identifier="anchor1"/>
+  <guideline y="500" name="x-height"/>
+  <image fileName="sketch.png" xScale="0.5"/>
+  <outline>
+    <contour identifier="contour1">
+      <point x="0" y="0" type="line" name="corner"/>
+      <point x="600" y="0" type="line"/>
+      <point x="300" y="700" type="line"/>
+    </contour>
+    <component base="acutecomb" xOffset="120"/>
+  </outline>
+  <lib>
+    <dict>
+      <key>com.example.string</key>
+      <string>plain</string>
+      <key>com.example.escaped</key>
+      <string>a &lt; b</string>
+      <key>com.example.array</key>
+      <array>
+        <integer>-3</integer>
+        <real>1.5</real>
+        <true/>
+        <data>AQID</data>
+        <dict/>
+      </array>
+    </dict>
+  </lib>
+  <note>
+  A note.
+  </note>
+</glyph>
+"#;
+
+    #[test]
+    fn borrows_from_input() {
+        let glyph = parse_glif_ref(GLIF.as_bytes()).unwrap();
+
+        assert!(matches!(glyph.name, Cow::Owned(_)));
+        assert_eq!(glyph.name, "A&B");
+        assert!(matches!(glyph.note, Some(Cow::Borrowed("\n  A note.\n  "))));
+        assert!(matches!(glyph.anchors[0].name, Some(Cow::Borrowed("top"))));
+        assert!(matches!(
+            glyph.contours[0].points[0].name,
+            Some(Cow::Borrowed("corner"))
+        ));
+        assert!(matches!(
+            glyph.components[0].base,
+            Cow::Borrowed("acutecomb")
+        ));
+        assert!(matches!(
+            glyph.lib[0].0,
+            Cow::Borrowed("com.example.string")
+        ));
+        assert!(matches!(
+            glyph.lib[0].1,
+            ValueRef::String(Cow::Borrowed("plain"))
+        ));
+        assert_eq!(glyph.lib[1].1, ValueRef::String(Cow::Owned("a < b".into())));
+        assert_eq!(
+            glyph.lib[2].1,
+            ValueRef::Array(vec![
+                ValueRef::Integer((-3).into()),
+                ValueRef::Real(1.5),
+                ValueRef::Boolean(true),
+                ValueRef::Data(vec![1, 2, 3]),
+                ValueRef::Dictionary(Vec::new()),
+            ])
+        );
+    }
+
+    #[test]
+    fn to_owned_matches_parse_glif() {
+        let glyph = parse_glif_ref(GLIF.as_bytes()).unwrap();
+        assert_eq!(
+            glyph.to_owned(),
+            crate::parse_glif(GLIF.as_bytes()).unwrap()
+        );
+    }
+
+    #[test]
+    fn validation() {
+        let bad_identifier = GLIF.replace("anchor1", "contour1");
+        assert!(parse_glif_ref(bad_identifier.as_bytes()).is_err());
+
+        let bad_lib = GLIF.replace("<true/>", "<true>yes</true>");
+        assert!(parse_glif_ref(bad_lib.as_bytes()).is_err());
+
+        let bad_outline = GLIF.replace(r#"type="line" name="corner""#, r#"name="corner""#);
+        assert!(matches!(
+            parse_glif_ref(bad_outline.as_bytes()),
+            Err(Error::Parse(ErrorKind::Outline(_)))
+        ));
+
+        let bad_date = GLIF.replace("<data>AQID</data>", "<date>yesterday</date>");
+        assert!(matches!(
+            parse_glif_ref(bad_date.as_bytes()),
+            Err(Error::Parse(ErrorKind::ParsePlist(_)))
+        ));
+
+        // An element inside a string is a lib error like with parse_glif, not a
+        // note error, even though the text is read the same way.
+        let element_in_string = GLIF.replace("<string>plain</string>", "<string>x<b/></string>");
+        assert!(matches!(
+            parse_glif_ref(element_in_string.as_bytes()),
+            Err(Error::Parse(ErrorKind::ParsePlist(_)))
+        ));
+        assert!(matches!(
+            crate::parse_glif(element_in_string.as_bytes()),
+            Err(Error::Parse(ErrorKind::ParsePlist(_)))
+        ));
+    }
+
+    #[test]
+    fn options() {
+        let options = ParseOptions {
+            limits: Limits {
+                max_string_length: 8,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(matches!(
+            parse_glif_ref_with_options(GLIF.as_bytes(), &options),
+            Err(Error::Parse(ErrorKind::LimitExceeded { .. }))
+        ));
+
+        let options = ParseOptions {
+            skip_lib: true,
+            ..Default::default()
+        };
+        let empty_contour = GLIF.replace("</contour>", "</contour>\n    <contour/>");
+        let (glyph, warnings) =
+            parse_glif_ref_with_options(empty_contour.as_bytes(), &options).unwrap();
+        assert!(glyph.lib.is_empty());
+        assert_eq!(glyph.contours.len(), 1);
+        assert_eq!(
+            warnings,
+            vec![Warning::EmptyContour {
+                contour: 1,
+                identifier: None
+            }]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Bounding boxes and sidebearings of parsed glyphs.
+
+use norad::{Contour, Glyph, PointType};
+
+use crate::decompose::decompose;
+use crate::geometry::segments;
+use crate::{ComponentError, GlyphSource};
+
+/// The key of the glyph lib entry holding the y coordinate of the vertical origin.
+const VERTICAL_ORIGIN_KEY: &str = "public.verticalOrigin";
+
+/// An axis-aligned rectangle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x_min: f64,
+    pub y_min: f64,
+    pub x_max: f64,
+    pub y_max: f64,
+}
+
+impl Rect {
+    fn from_point((x, y): (f64, f64)) -> Self {
+        Rect {
+            x_min: x,
+            y_min: y,
+            x_max: x,
+            y_max: y,
+        }
+    }
+
+    fn include(&mut self, (x, y): (f64, f64)) {
+        self.x_min = self.x_min.min(x);
+        self.y_min = self.y_min.min(y);
+        self.x_max = self.x_max.max(x);
+        self.y_max = self.y_max.max(y);
+    }
+
+    /// The smallest rectangle containing both `self` and `other`.
+    pub fn union(&self, other: &Rect) -> Rect {
+        Rect {
+            x_min: self.x_min.min(other.x_min),
+            y_min: self.y_min.min(other.y_min),
+            x_max: self.x_max.max(other.x_max),
+            y_max: self.y_max.max(other.y_max),
+        }
+    }
+}
+
+/// The space between a glyph's outline and the edges of its advance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sidebearings {
+    /// The distance from 0 to the left edge of the bounds.
+    pub left: f64,
+    /// The distance from the right edge of the bounds to the advance width.
+    pub right: f64,
+    /// The distance from the top of the bounds down from the vertical origin, if the
+    /// glyph sets `public.verticalOrigin` in its lib.
+    pub top: Option<f64>,
+    /// The distance from the bottom of the vertical advance, i.e. the vertical origin
+    /// minus the advance height, up to the bottom of the bounds, if the glyph sets
+    /// `public.verticalOrigin` in its lib.
+    pub bottom: Option<f64>,
+}
+
+/// The exact bounds of a contour, including the extrema of its curves.
+///
+/// Returns `None` for a contour without points. Off-curve points only count where
+/// the curves they shape reach.
+pub fn contour_bounds(contour: &Contour) -> Option<Rect> {
+    let mut bounds: Option<Rect> = None;
+    let mut include = |point| match &mut bounds {
+        Some(bounds) => bounds.include(point),
+        None => bounds = Some(Rect::from_point(point)),
+    };
+    for point in &contour.points {
+        if point.typ != PointType::OffCurve {
+            include((point.x, point.y));
+        }
+    }
+    for segment in segments(contour) {
+        let (start, end) = segment.ends();
+        include(start);
+        include(end);
+        for t in segment.extrema() {
+            include(segment.point_at(t));
+        }
+    }
+    bounds
+}
+
+/// The exact bounds of a glyph's outline, including the contours of its components
+/// as looked up in `layer`.
+///
+/// Returns `None` for a glyph without points. Errors when a component refers to a
+/// missing glyph or components form a cycle.
+pub fn glyph_bounds(
+    glyph: &Glyph,
+    layer: &impl GlyphSource,
+) -> Result<Option<Rect>, ComponentError> {
+    Ok(decompose(glyph, layer)?
+        .iter()
+        .filter_map(contour_bounds)
+        .reduce(|a, b| a.union(&b)))
+}
+
+/// The sidebearings of a glyph, from its [bounds](glyph_bounds) and advance.
+///
+/// The vertical sidebearings are only known if the glyph has a `public.verticalOrigin`
+/// number in its lib. Returns `None` for a glyph without points.
+pub fn sidebearings(
+    glyph: &Glyph,
+    layer: &impl GlyphSource,
+) -> Result<Option<Sidebearings>, ComponentError> {
+    let bounds = match glyph_bounds(glyph, layer)? {
+        Some(bounds) => bounds,
+        None => return Ok(None),
+    };
+    let vertical_origin = glyph.lib.get(VERTICAL_ORIGIN_KEY).and_then(|value| {
+        value
+            .as_real()
+            .or_else(|| value.as_signed_integer().map(|i| i as f64))
+    });
+    Ok(Some(Sidebearings {
+        left: bounds.x_min,
+        right: glyph.width - bounds.x_max,
+        top: vertical_origin.map(|origin| origin - bounds.y_max),
+        bottom: vertical_origin.map(|origin| bounds.y_min - (origin - glyph.height)),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use norad::{AffineTransform, Component};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test_util::{contour, layer};
+
+    #[test]
+    fn curve_extrema() {
+        use PointType::*;
+
+        // A cubic bulging to x = 75 and a quadratic dipping to y = -50.
+        let cubic = contour(&[
+            (0.0, 0.0, Line),
+            (100.0, 0.0, OffCurve),
+            (100.0, 100.0, OffCurve),
+            (0.0, 100.0, Curve),
+        ]);
+        assert_eq!(
+            contour_bounds(&cubic),
+            Some(Rect {
+                x_min: 0.0,
+                y_min: 0.0,
+                x_max: 75.0,
+                y_max: 100.0,
+            })
+        );
+
+        let quadratic = contour(&[
+            (0.0, 0.0, Move),
+            (50.0, -100.0, OffCurve),
+            (100.0, 0.0, QCurve),
+        ]);
+        assert_eq!(
+            contour_bounds(&quadratic),
+            Some(Rect {
+                x_min: 0.0,
+                y_min: -50.0,
+                x_max: 100.0,
+                y_max: 0.0,
+            })
+        );
+
+        // Off-curve points only; the curve touches the sides of the square at their
+        // midpoints.
+        let round = contour(&[
+            (0.0, 0.0, OffCurve),
+            (0.0, 100.0, OffCurve),
+            (100.0, 100.0, OffCurve),
+            (100.0, 0.0, OffCurve),
+        ]);
+        assert_eq!(
+            contour_bounds(&round),
+            Some(Rect {
+                x_min: 0.0,
+                y_min: 0.0,
+                x_max: 100.0,
+                y_max: 100.0,
+            })
+        );
+        assert_eq!(contour_bounds(&contour(&[])), None);
+    }
+
+    #[test]
+    fn glyph_sidebearings() {
+        let mut bar = Glyph::new_named("bar");
+        bar.contours = vec![contour(&[
+            (0.0, 0.0, PointType::Line),
+            (0.0, 700.0, PointType::Line),
+            (80.0, 700.0, PointType::Line),
+            (80.0, 0.0, PointType::Line),
+        ])];
+        let mut composite = Glyph::new_named("composite");
+        composite.width = 300.0;
+        composite.height = 1000.0;
+        composite.components = vec![Component::new(
+            "bar".into(),
+            AffineTransform {
+                x_offset: 100.0,
+                y_offset: -100.0,
+                ..Default::default()
+            },
+            None,
+            None,
+        )];
+        let layer = layer([bar, composite]);
+
+        let mut composite = layer["composite"].clone();
+        assert_eq!(
+            sidebearings(&composite, &layer),
+            Ok(Some(Sidebearings {
+                left: 100.0,
+                right: 120.0,
+                top: None,
+                bottom: None,
+            }))
+        );
+
+        composite.lib.insert(
+            VERTICAL_ORIGIN_KEY.into(),
+            plist::Value::Integer(880.into()),
+        );
+        assert_eq!(
+            sidebearings(&composite, &layer),
+            Ok(Some(Sidebearings {
+                left: 100.0,
+                right: 120.0,
+                top: Some(280.0),
+                bottom: Some(20.0),
+            }))
+        );
+        assert_eq!(sidebearings(&Glyph::new_named("space"), &layer), Ok(None));
+    }
+}
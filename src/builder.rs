@@ -1,43 +1,232 @@
-//! A builder for outlines.
+//! Builders for glyphs and their outlines.
 //!
 //! An [`OutlineBuilder`] is a point-oriented builder for a glyph's graphical outline,
 //! not unlike a [fontTools point pen], but different, because it does not draw _into_ a
-//! glyph due to ownership issues.
+//! glyph due to ownership issues. It checks point sequences, but does not keep track of
+//! identifier uniqueness.
 //!
-//! To be used internally by [`super::parse::GlifParser`]. Does not keep track of identifier
-//! uniqueness (`GlifParser` has to).
+//! A [`GlyphBuilder`] puts together a whole glyph, outline included, and checks it the
+//! way [`crate::parse_glif`] checks a glif file.
 //!
 //! [fontTools point pen]: https://fonttools.readthedocs.io/en/latest/pens/basePen.html
 
+use std::collections::HashSet;
+
 use norad::{
-    error::ErrorKind, AffineTransform, Component, Contour, ContourPoint, GlyphName, Identifier,
-    PointType,
+    error::ErrorKind, AffineTransform, Anchor, Component, Contour, ContourPoint, GlifVersion,
+    Glyph, GlyphName, Guideline, Identifier, Image, Line, Plist, PointType,
 };
 
-#[derive(Debug, Default)]
-pub(crate) struct OutlineBuilder {
-    components: Vec<Component>,
-    contours: Vec<Contour>,
-    scratch_state: OutlineBuilderState,
+use crate::Warning;
+
+/// An error from an [`OutlineBuilder`], with where in the outline it happened.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineError {
+    /// What went wrong.
+    pub kind: ErrorKind,
+    /// The index of the contour being drawn, counting every [`OutlineBuilder::begin_path`].
+    ///
+    /// `None` when no contour was being drawn.
+    pub contour: Option<usize>,
+    /// The index of the offending point within the contour, if a point was at fault.
+    pub point: Option<usize>,
+    /// The coordinates of the offending point.
+    pub position: Option<(f64, f64)>,
 }
 
-#[derive(Debug)]
-enum OutlineBuilderState {
-    Idle,
-    Drawing {
-        scratch_contour: Contour,
-        number_of_offcurves: u32,
-    },
+impl OutlineError {
+    pub(crate) fn new(kind: ErrorKind, contour: Option<usize>) -> Self {
+        OutlineError {
+            kind,
+            contour,
+            point: None,
+            position: None,
+        }
+    }
+
+    fn at_point(kind: ErrorKind, contour: usize, point: usize, position: (f64, f64)) -> Self {
+        OutlineError {
+            kind,
+            contour: Some(contour),
+            point: Some(point),
+            position: Some(position),
+        }
+    }
 }
 
-impl Default for OutlineBuilderState {
-    fn default() -> Self {
-        OutlineBuilderState::Idle
+impl std::fmt::Display for OutlineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)?;
+        match (self.contour, self.point, self.position) {
+            (Some(contour), Some(point), Some((x, y))) => {
+                write!(f, " at contour {}, point {} ({}, {})", contour, point, x, y)
+            }
+            (Some(contour), _, _) => write!(f, " at contour {}", contour),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for OutlineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
     }
 }
 
+/// The rules for point sequences, for builders that keep the points themselves.
+///
+/// Tracks the contour being drawn by its number of points and trailing off-curve
+/// points only, so that [`OutlineBuilder`] and the parser's other collectors can store
+/// points however they like.
+#[derive(Debug, Default)]
+pub(crate) struct PointSequence {
+    /// The number of paths begun so far, for error locations.
+    number_of_paths: usize,
+    /// The number of points and of trailing off-curve points of the path being drawn.
+    drawing: Option<(usize, u32)>,
+}
+
+impl PointSequence {
+    /// The index of the path being drawn.
+    fn current_path(&self) -> usize {
+        self.number_of_paths - 1
+    }
+
+    /// Begin a new path. Errors when a path has been begun already but not ended yet.
+    pub(crate) fn begin_path(&mut self) -> Result<(), OutlineError> {
+        if self.drawing.is_some() {
+            return Err(OutlineError::new(
+                ErrorKind::UnfinishedDrawing,
+                Some(self.current_path()),
+            ));
+        }
+        self.drawing = Some((0, 0));
+        self.number_of_paths += 1;
+        Ok(())
+    }
+
+    /// Check the next point of the path being drawn, see [`OutlineBuilder::add_point`].
+    pub(crate) fn add_point(
+        &mut self,
+        position: (f64, f64),
+        segment_type: PointType,
+        smooth: bool,
+    ) -> Result<(), OutlineError> {
+        let (number_of_points, number_of_offcurves) = self
+            .drawing
+            .as_mut()
+            .ok_or_else(|| OutlineError::new(ErrorKind::PenPathNotStarted, None))?;
+        let contour = self.number_of_paths - 1;
+        let error = |kind| OutlineError::at_point(kind, contour, *number_of_points, position);
+        match segment_type {
+            PointType::Move => {
+                if *number_of_points > 0 {
+                    return Err(error(ErrorKind::UnexpectedMove));
+                }
+            }
+            PointType::Line => {
+                if *number_of_offcurves > 0 {
+                    return Err(error(ErrorKind::UnexpectedPointAfterOffCurve));
+                }
+            }
+            PointType::OffCurve => {
+                if smooth {
+                    return Err(error(ErrorKind::UnexpectedSmooth));
+                }
+                *number_of_offcurves = number_of_offcurves.saturating_add(1)
+            }
+            PointType::QCurve => *number_of_offcurves = 0,
+            PointType::Curve => {
+                if *number_of_offcurves > 2 {
+                    return Err(error(ErrorKind::TooManyOffCurves));
+                }
+                *number_of_offcurves = 0;
+            }
+        }
+        *number_of_points += 1;
+        Ok(())
+    }
+
+    /// End the path being drawn, see [`OutlineBuilder::end_path`]. `point` gives the
+    /// position and type of the path's points by index.
+    ///
+    /// Returns the index of the path.
+    pub(crate) fn end_path(
+        &mut self,
+        point: impl Fn(usize) -> ((f64, f64), PointType),
+    ) -> Result<usize, OutlineError> {
+        let (number_of_points, mut number_of_offcurves) = self
+            .drawing
+            .take()
+            .ok_or_else(|| OutlineError::new(ErrorKind::PenPathNotStarted, None))?;
+        let contour = self.current_path();
+        let error = |kind, index: usize| {
+            let (position, _) = point(index);
+            OutlineError::at_point(kind, contour, index, position)
+        };
+        // If ending a closed contour with off-curve points, wrap around and check
+        // from the beginning that we have a curve or qcurve following eventually.
+        //
+        // A closed contour of nothing but off-curve points has no such point to
+        // wrap around to. It is a quadratic curve with implied on-curve points
+        // between all of them, as found in TrueType outlines. Cubic curves can't
+        // have implied on-curve points, so an off-curve run wrapping around to a
+        // curve point must be checked like any other.
+        if number_of_offcurves > 0 {
+            if point(0).1 == PointType::Move {
+                return Err(error(ErrorKind::TrailingOffCurves, number_of_points - 1));
+            }
+            let all_offcurves = number_of_offcurves as usize == number_of_points;
+            if !all_offcurves {
+                for index in 0..number_of_points {
+                    match point(index).1 {
+                        PointType::OffCurve => {
+                            number_of_offcurves = number_of_offcurves.saturating_add(1)
+                        }
+                        PointType::QCurve => break,
+                        PointType::Curve => {
+                            if number_of_offcurves > 2 {
+                                return Err(error(ErrorKind::TooManyOffCurves, index));
+                            }
+                            break;
+                        }
+                        PointType::Line => {
+                            return Err(error(ErrorKind::UnexpectedPointAfterOffCurve, index));
+                        }
+                        PointType::Move => unreachable!(),
+                    }
+                }
+            }
+        }
+        Ok(contour)
+    }
+
+    /// Check that no path is being drawn anymore.
+    pub(crate) fn finish(&self) -> Result<(), OutlineError> {
+        match self.drawing {
+            None => Ok(()),
+            Some(_) => Err(OutlineError::new(
+                ErrorKind::UnfinishedDrawing,
+                Some(self.current_path()),
+            )),
+        }
+    }
+}
+
+/// A builder for a glyph's contours and components.
+#[derive(Debug, Default)]
+pub struct OutlineBuilder {
+    components: Vec<Component>,
+    contours: Vec<Contour>,
+    sequence: PointSequence,
+    /// The contour being drawn.
+    scratch_contour: Option<Contour>,
+    warnings: Vec<Warning>,
+}
+
 impl OutlineBuilder {
-    pub(crate) fn new() -> Self {
+    /// Create an empty outline builder.
+    pub fn new() -> Self {
         Default::default()
     }
 
@@ -50,20 +239,13 @@ impl OutlineBuilder {
     ///
     /// On error, it won't begin a new path and you can continue drawing the previously
     /// started path.
-    pub(crate) fn begin_path(
+    pub fn begin_path(
         &mut self,
         identifier: Option<Identifier>,
-    ) -> Result<&mut Self, ErrorKind> {
-        match self.scratch_state {
-            OutlineBuilderState::Idle => {
-                self.scratch_state = OutlineBuilderState::Drawing {
-                    scratch_contour: Contour::new(Vec::new(), identifier, None),
-                    number_of_offcurves: 0,
-                };
-                Ok(self)
-            }
-            OutlineBuilderState::Drawing { .. } => Err(ErrorKind::UnfinishedDrawing),
-        }
+    ) -> Result<&mut Self, OutlineError> {
+        self.sequence.begin_path()?;
+        self.scratch_contour = Some(Contour::new(Vec::new(), identifier, None));
+        Ok(self)
     }
 
     /// Add a point to the path begun by `begin_path()`.
@@ -75,61 +257,34 @@ impl OutlineBuilder {
     ///
     /// On error, it won't add any part of the point, but you can try again with a new
     /// and improved point.
-    pub(crate) fn add_point(
+    pub fn add_point(
         &mut self,
         (x, y): (f64, f64),
         segment_type: PointType,
         smooth: bool,
         name: Option<String>,
         identifier: Option<Identifier>,
-    ) -> Result<&mut Self, ErrorKind> {
-        match &mut self.scratch_state {
-            OutlineBuilderState::Idle => Err(ErrorKind::PenPathNotStarted),
-            OutlineBuilderState::Drawing {
-                scratch_contour,
-                number_of_offcurves,
-            } => {
-                match segment_type {
-                    PointType::Move => {
-                        if !scratch_contour.points.is_empty() {
-                            return Err(ErrorKind::UnexpectedMove);
-                        }
-                    }
-                    PointType::Line => {
-                        if *number_of_offcurves > 0 {
-                            return Err(ErrorKind::UnexpectedPointAfterOffCurve);
-                        }
-                    }
-                    PointType::OffCurve => {
-                        if smooth {
-                            return Err(ErrorKind::UnexpectedSmooth);
-                        }
-                        *number_of_offcurves = number_of_offcurves.saturating_add(1)
-                    }
-                    PointType::QCurve => *number_of_offcurves = 0,
-                    PointType::Curve => {
-                        if *number_of_offcurves > 2 {
-                            return Err(ErrorKind::TooManyOffCurves);
-                        }
-                        *number_of_offcurves = 0;
-                    }
-                }
-                scratch_contour.points.push(ContourPoint::new(
-                    x,
-                    y,
-                    segment_type,
-                    smooth,
-                    name,
-                    identifier,
-                    None,
-                ));
-                Ok(self)
-            }
-        }
+    ) -> Result<&mut Self, OutlineError> {
+        self.sequence.add_point((x, y), segment_type, smooth)?;
+        // The sequence only accepts points while a path is being drawn.
+        let scratch_contour = self.scratch_contour.as_mut().unwrap();
+        scratch_contour.points.push(ContourPoint::new(
+            x,
+            y,
+            segment_type,
+            smooth,
+            name,
+            identifier,
+            None,
+        ));
+        Ok(self)
     }
 
     /// Ends the path begun by [`Self::begin_path`] and adds the contour to the glyph's
-    /// outline, unless it's empty.
+    /// outline, unless it's empty. Empty contours are noted in [`Self::warnings`].
+    ///
+    /// A closed contour may consist of off-curve points only, in which case it is a
+    /// quadratic curve with implied on-curve points.
     ///
     /// Errors when:
     /// 1. [`Self::begin_path`] wasn't called first.
@@ -137,50 +292,29 @@ impl OutlineBuilder {
     ///
     /// On error, it drops the path you were trying to end and you can
     /// [`Self::begin_path`] again. It doesn't change the previously added paths.
-    pub(crate) fn end_path(&mut self) -> Result<&mut Self, ErrorKind> {
-        match std::mem::replace(&mut self.scratch_state, OutlineBuilderState::Idle) {
-            OutlineBuilderState::Idle => Err(ErrorKind::PenPathNotStarted),
-            OutlineBuilderState::Drawing {
-                scratch_contour,
-                mut number_of_offcurves,
-            } => {
-                // If ending a closed contour with off-curve points, wrap around and check
-                // from the beginning that we have a curve or qcurve following eventually.
-                if number_of_offcurves > 0 {
-                    if scratch_contour.is_closed() {
-                        for point in &scratch_contour.points {
-                            match point.typ {
-                                PointType::OffCurve => {
-                                    number_of_offcurves = number_of_offcurves.saturating_add(1)
-                                }
-                                PointType::QCurve => break,
-                                PointType::Curve => {
-                                    if number_of_offcurves > 2 {
-                                        return Err(ErrorKind::TooManyOffCurves);
-                                    }
-                                    break;
-                                }
-                                PointType::Line => {
-                                    return Err(ErrorKind::UnexpectedPointAfterOffCurve);
-                                }
-                                PointType::Move => unreachable!(),
-                            }
-                        }
-                    } else {
-                        return Err(ErrorKind::TrailingOffCurves);
-                    }
-                }
-                // Empty contours are allowed by the specification but make no sense, skip them.
-                if !scratch_contour.points.is_empty() {
-                    self.contours.push(scratch_contour);
-                }
-                Ok(self)
-            }
+    pub fn end_path(&mut self) -> Result<&mut Self, OutlineError> {
+        let scratch_contour = self.scratch_contour.take();
+        let points = scratch_contour.as_ref().map_or(&[][..], |c| &c.points[..]);
+        let contour = self.sequence.end_path(|index| {
+            let point = &points[index];
+            ((point.x, point.y), point.typ)
+        })?;
+        let scratch_contour = scratch_contour.unwrap();
+        // Empty contours are allowed by the specification but make no sense, skip
+        // them. Note it, as their identifier may be referred to elsewhere.
+        if scratch_contour.points.is_empty() {
+            self.warnings.push(Warning::EmptyContour {
+                contour,
+                identifier: scratch_contour.identifier().cloned(),
+            });
+        } else {
+            self.contours.push(scratch_contour);
         }
+        Ok(self)
     }
 
     /// Add a component to the glyph.
-    pub(crate) fn add_component(
+    pub fn add_component(
         &mut self,
         base: GlyphName,
         transform: AffineTransform,
@@ -191,17 +325,214 @@ impl OutlineBuilder {
         self
     }
 
+    /// The problems noted so far, i.e. the empty contours that were dropped.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
     /// Consume the builder and return the final [`Contour`]s and [`Component`]s.
     ///
     /// Errors when a path has been begun but not ended.
     ///
     /// On error, it won't finish the outline and return it to you, but you can
     /// [`Self::end_path`] before trying to finish again.
-    pub(crate) fn finish(self) -> Result<(Vec<Contour>, Vec<Component>), ErrorKind> {
-        match self.scratch_state {
-            OutlineBuilderState::Idle => Ok((self.contours, self.components)),
-            OutlineBuilderState::Drawing { .. } => Err(ErrorKind::UnfinishedDrawing),
+    pub fn finish(self) -> Result<(Vec<Contour>, Vec<Component>), OutlineError> {
+        self.sequence.finish()?;
+        Ok((self.contours, self.components))
+    }
+}
+
+/// A builder for a whole glyph.
+///
+/// Enforces the rules [`crate::parse_glif`] enforces for glif files: elements that may
+/// only appear once can only be set once, codepoints and identifiers must be unique,
+/// format 1 glyphs can't have identifiers, and outlines must have valid point
+/// sequences.
+///
+/// Like the parser, it keeps the format it was given and leaves the single-point
+/// contours that format 1 uses for anchors as they are.
+#[derive(Debug)]
+pub struct GlyphBuilder {
+    glyph: Glyph,
+    identifiers: HashSet<Identifier>,
+    seen_advance: bool,
+    seen_outline: bool,
+    seen_lib: bool,
+}
+
+impl GlyphBuilder {
+    /// Start building a glyph named `name` in the given format.
+    pub fn new(name: impl Into<GlyphName>, format: GlifVersion) -> Self {
+        let mut glyph = Glyph::new_named(name);
+        glyph.format = format;
+        GlyphBuilder {
+            glyph,
+            identifiers: HashSet::new(),
+            seen_advance: false,
+            seen_outline: false,
+            seen_lib: false,
+        }
+    }
+
+    /// Set the advance width and height.
+    ///
+    /// Errors when the advance has been set already.
+    pub fn advance(&mut self, width: f64, height: f64) -> Result<&mut Self, crate::ErrorKind> {
+        if self.seen_advance {
+            return Err(crate::ErrorKind::DuplicateElement);
         }
+        self.seen_advance = true;
+        self.glyph.width = width;
+        self.glyph.height = height;
+        Ok(self)
+    }
+
+    /// Add a codepoint.
+    ///
+    /// Errors when the codepoint has been added already.
+    pub fn unicode(&mut self, codepoint: char) -> Result<&mut Self, crate::ErrorKind> {
+        if self.glyph.codepoints.contains(&codepoint) {
+            return Err(crate::ErrorKind::DuplicateCodepoint(codepoint));
+        }
+        self.glyph.codepoints.push(codepoint);
+        Ok(self)
+    }
+
+    /// Add an anchor.
+    ///
+    /// Errors when its identifier is not allowed or not unique.
+    pub fn anchor(&mut self, anchor: Anchor) -> Result<&mut Self, crate::ErrorKind> {
+        self.check_identifier(anchor.identifier())?;
+        self.glyph.anchors.push(anchor);
+        Ok(self)
+    }
+
+    /// Add a guideline.
+    ///
+    /// Errors when its angle is outside of 0 to 360 degrees or its identifier is not
+    /// allowed or not unique.
+    pub fn guideline(&mut self, guideline: Guideline) -> Result<&mut Self, crate::ErrorKind> {
+        if let Line::Angle { degrees, .. } = guideline.line {
+            if !(0.0..=360.0).contains(&degrees) {
+                return Err(crate::ErrorKind::InvalidAngle);
+            }
+        }
+        self.check_identifier(guideline.identifier())?;
+        self.glyph.guidelines.push(guideline);
+        Ok(self)
+    }
+
+    /// Set the background image.
+    ///
+    /// Errors when the image has been set already or its file name is not a bare file
+    /// name.
+    pub fn image(&mut self, image: Image) -> Result<&mut Self, crate::ErrorKind> {
+        if self.glyph.image.is_some() {
+            return Err(crate::ErrorKind::DuplicateElement);
+        }
+        let file_name = image.file_name.to_string_lossy();
+        if !crate::is_bare_file_name(&file_name) {
+            return Err(crate::ErrorKind::InvalidImageFileName(
+                file_name.into_owned(),
+            ));
+        }
+        self.glyph.image = Some(image);
+        Ok(self)
+    }
+
+    /// Set the outline, e.g. as returned by [`OutlineBuilder::finish`].
+    ///
+    /// Errors when the outline has been set already, a contour's point sequence is
+    /// forbidden by the specification, a component has no base glyph or an
+    /// identifier is not allowed or not unique.
+    pub fn outline(
+        &mut self,
+        contours: Vec<Contour>,
+        components: Vec<Component>,
+    ) -> Result<&mut Self, crate::ErrorKind> {
+        if self.seen_outline {
+            return Err(crate::ErrorKind::DuplicateElement);
+        }
+
+        // Check everything before changing anything, so that the builder stays usable.
+        let mut identifiers = self.identifiers.clone();
+        let mut check_identifier =
+            |identifier| check_identifier(&mut identifiers, self.glyph.format, identifier);
+        // One scratch builder for all contours, so that errors point at the right one.
+        let mut scratch = OutlineBuilder::new();
+        for contour in &contours {
+            check_identifier(contour.identifier())?;
+            scratch.begin_path(None)?;
+            for point in &contour.points {
+                check_identifier(point.identifier())?;
+                scratch.add_point((point.x, point.y), point.typ, point.smooth, None, None)?;
+            }
+            scratch.end_path()?;
+        }
+        for component in &components {
+            if component.base.is_empty() {
+                return Err(crate::ErrorKind::InvalidComponent);
+            }
+            check_identifier(component.identifier())?;
+        }
+        self.identifiers = identifiers;
+        self.seen_outline = true;
+
+        self.glyph.contours = contours;
+        self.glyph.components = components;
+        Ok(self)
+    }
+
+    /// Set the glyph lib.
+    ///
+    /// Errors when the lib has been set already.
+    pub fn lib(&mut self, lib: Plist) -> Result<&mut Self, crate::ErrorKind> {
+        if self.seen_lib {
+            return Err(crate::ErrorKind::DuplicateElement);
+        }
+        self.seen_lib = true;
+        self.glyph.lib = lib;
+        Ok(self)
+    }
+
+    /// Set the note.
+    ///
+    /// Errors when the note has been set already.
+    pub fn note(&mut self, note: impl Into<String>) -> Result<&mut Self, crate::ErrorKind> {
+        if self.glyph.note.is_some() {
+            return Err(crate::ErrorKind::DuplicateElement);
+        }
+        self.glyph.note = Some(note.into());
+        Ok(self)
+    }
+
+    /// Consume the builder and return the glyph.
+    pub fn finish(self) -> Glyph {
+        self.glyph
+    }
+
+    fn check_identifier(
+        &mut self,
+        identifier: Option<&Identifier>,
+    ) -> Result<(), crate::ErrorKind> {
+        check_identifier(&mut self.identifiers, self.glyph.format, identifier)
+    }
+}
+
+/// Check that an identifier is allowed in the glif format and unique in the glyph.
+fn check_identifier(
+    identifiers: &mut HashSet<Identifier>,
+    format: GlifVersion,
+    identifier: Option<&Identifier>,
+) -> Result<(), crate::ErrorKind> {
+    match identifier {
+        None => Ok(()),
+        // The parser reports identifiers in format 1 files as unexpected attributes.
+        Some(_) if format == GlifVersion::V1 => Err(crate::ErrorKind::UnexpectedAttribute),
+        Some(identifier) if !identifiers.insert(identifier.clone()) => {
+            Err(crate::ErrorKind::DuplicateIdentifier)
+        }
+        Some(_) => Ok(()),
     }
 }
 
@@ -210,7 +541,7 @@ mod tests {
     use super::*;
 
     #[test]
-    fn builder_basic() -> Result<(), ErrorKind> {
+    fn builder_basic() -> Result<(), OutlineError> {
         let mut outline_builder = OutlineBuilder::new();
         outline_builder
             .begin_path(Some(Identifier::new("abc").unwrap()))?
@@ -274,39 +605,185 @@ mod tests {
         Ok(())
     }
 
-    // #[test]
-    // fn glyph_builder_upgrade_v1_anchor() -> Result<(), ErrorKind> {
-    //     let mut builder = GlyphBuilder::new("test", GlifVersion::V1);
-
-    //     let mut outline_builder = OutlineBuilder::new();
-    //     outline_builder
-    //         .begin_path(None)?
-    //         .add_point((173.0, 536.0), PointType::Move, false, Some("top".into()), None)?
-    //         .end_path()?;
-    //     let (outline, identifiers) = outline_builder.finish()?;
-    //     builder.outline(outline, identifiers)?;
-    //     let glyph = builder.finish()?;
-
-    //     assert_eq!(
-    //         glyph,
-    //         Glyph {
-    //             name: "test".into(),
-    //             format: GlifVersion::V2,
-    //             height: 0.,
-    //             width: 0.,
-    //             codepoints: Vec::new(),
-    //             note: None,
-    //             guidelines: Vec::new(),
-    //             anchors: vec![Anchor::new(173.0, 536.0, Some("top".into()), None, None, None)],
-    //             components: Vec::new(),
-    //             contours: Vec::new(),
-    //             image: None,
-    //             lib: Plist::new(),
-    //         }
-    //     );
-
-    //     Ok(())
-    // }
+    #[test]
+    fn all_offcurve_contours() -> Result<(), OutlineError> {
+        // A TrueType circle: four off-curve points with implied on-curve points.
+        let mut outline_builder = OutlineBuilder::new();
+        outline_builder
+            .begin_path(None)?
+            .add_point((0.0, 100.0), PointType::OffCurve, false, None, None)?
+            .add_point((100.0, 100.0), PointType::OffCurve, false, None, None)?
+            .add_point((100.0, 0.0), PointType::OffCurve, false, None, None)?
+            .add_point((0.0, 0.0), PointType::OffCurve, false, None, None)?
+            .end_path()?;
+        let (contours, _) = outline_builder.finish()?;
+        assert_eq!(contours[0].points.len(), 4);
+
+        // The cubic equivalent, three off-curve points wrapping around to a curve point.
+        let mut outline_builder = OutlineBuilder::new();
+        outline_builder
+            .begin_path(None)?
+            .add_point((0.0, 0.0), PointType::Curve, false, None, None)?
+            .add_point((0.0, 100.0), PointType::OffCurve, false, None, None)?
+            .add_point((100.0, 100.0), PointType::OffCurve, false, None, None)?
+            .add_point((100.0, 0.0), PointType::OffCurve, false, None, None)?;
+        assert_eq!(
+            outline_builder.end_path().unwrap_err(),
+            OutlineError {
+                kind: ErrorKind::TooManyOffCurves,
+                contour: Some(0),
+                point: Some(0),
+                position: Some((0.0, 0.0)),
+            }
+        );
+
+        // Likewise when the off-curve run spans the start of the contour.
+        let mut outline_builder = OutlineBuilder::new();
+        outline_builder
+            .begin_path(None)?
+            .add_point((0.0, 100.0), PointType::OffCurve, false, None, None)?
+            .add_point((100.0, 100.0), PointType::Curve, false, None, None)?
+            .add_point((100.0, 0.0), PointType::OffCurve, false, None, None)?
+            .add_point((0.0, 0.0), PointType::OffCurve, false, None, None)?;
+        assert_eq!(
+            outline_builder.end_path().unwrap_err().kind,
+            ErrorKind::TooManyOffCurves
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn glyph_builder_keeps_v1_anchor_contours() -> Result<(), crate::ErrorKind> {
+        let mut builder = GlyphBuilder::new("test", GlifVersion::V1);
+
+        let mut outline_builder = OutlineBuilder::new();
+        outline_builder
+            .begin_path(None)?
+            .add_point(
+                (173.0, 536.0),
+                PointType::Move,
+                false,
+                Some("top".into()),
+                None,
+            )?
+            .end_path()?;
+        let (contours, components) = outline_builder.finish()?;
+        builder.outline(contours.clone(), components)?;
+        let glyph = builder.finish();
+
+        let mut expected = Glyph::new_named("test");
+        expected.format = GlifVersion::V1;
+        expected.contours = contours;
+        assert_eq!(glyph, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn glyph_builder_rules() -> Result<(), crate::ErrorKind> {
+        let id = |s| Some(Identifier::new(s).unwrap());
+        let mut builder = GlyphBuilder::new("test", GlifVersion::V2);
+        builder
+            .advance(500.0, 0.0)?
+            .unicode('a')?
+            .anchor(Anchor::new(0.0, 0.0, None, None, id("abc"), None))?;
+
+        assert!(matches!(
+            builder.advance(600.0, 0.0),
+            Err(crate::ErrorKind::DuplicateElement)
+        ));
+        assert!(matches!(
+            builder.unicode('a'),
+            Err(crate::ErrorKind::DuplicateCodepoint('a'))
+        ));
+        assert!(matches!(
+            builder.guideline(Guideline::new(
+                Line::Horizontal(0.0),
+                None,
+                None,
+                id("abc"),
+                None
+            )),
+            Err(crate::ErrorKind::DuplicateIdentifier)
+        ));
+        assert!(matches!(
+            builder.guideline(Guideline::new(
+                Line::Angle {
+                    x: 0.0,
+                    y: 0.0,
+                    degrees: 400.0
+                },
+                None,
+                None,
+                None,
+                None
+            )),
+            Err(crate::ErrorKind::InvalidAngle)
+        ));
+
+        // A rejected outline doesn't count as set.
+        let bad_contour = Contour::new(
+            vec![
+                ContourPoint::new(0.0, 0.0, PointType::OffCurve, false, None, id("def"), None),
+                ContourPoint::new(0.0, 0.0, PointType::Line, false, None, None, None),
+            ],
+            None,
+            None,
+        );
+        assert!(matches!(
+            builder.outline(vec![bad_contour], Vec::new()),
+            Err(crate::ErrorKind::Outline(OutlineError {
+                kind: ErrorKind::UnexpectedPointAfterOffCurve,
+                contour: Some(0),
+                point: Some(1),
+                ..
+            }))
+        ));
+        let contour = Contour::new(
+            vec![ContourPoint::new(
+                0.0,
+                0.0,
+                PointType::Line,
+                false,
+                None,
+                id("def"),
+                None,
+            )],
+            None,
+            None,
+        );
+        // Errors point at the contour at fault, not just the first one.
+        let line = |x| ContourPoint::new(x, 0.0, PointType::Line, false, None, None, None);
+        let offcurve = ContourPoint::new(0.0, 0.0, PointType::OffCurve, false, None, None, None);
+        let good_contour = Contour::new(vec![line(0.0), line(10.0)], None, None);
+        let bad_contour = Contour::new(vec![offcurve, line(0.0)], None, None);
+        assert!(matches!(
+            builder.outline(
+                vec![good_contour.clone(), good_contour, bad_contour],
+                Vec::new()
+            ),
+            Err(crate::ErrorKind::Outline(OutlineError {
+                kind: ErrorKind::UnexpectedPointAfterOffCurve,
+                contour: Some(2),
+                point: Some(1),
+                ..
+            }))
+        ));
+        builder.outline(vec![contour], Vec::new())?;
+
+        let glyph = builder.finish();
+        assert_eq!(glyph.codepoints, vec!['a']);
+        assert_eq!(glyph.contours.len(), 1);
+
+        let mut v1 = GlyphBuilder::new("test", GlifVersion::V1);
+        assert!(matches!(
+            v1.anchor(Anchor::new(0.0, 0.0, None, None, id("abc"), None)),
+            Err(crate::ErrorKind::UnexpectedAttribute)
+        ));
+
+        Ok(())
+    }
 
     #[test]
     #[should_panic(expected = "UnfinishedDrawing")]
@@ -0,0 +1,263 @@
+//! Checking that the same glyph from several masters can be interpolated.
+
+use std::borrow::Borrow;
+
+use norad::{Glyph, GlyphName, PointType};
+
+/// The first difference found between a master and the first master.
+///
+/// Masters, contours, points, components and anchors are given by their index.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum Incompatibility {
+    #[error("master {master} has {found} contours, expected {expected}")]
+    ContourCount {
+        master: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error("master {master} has {found} points in contour {contour}, expected {expected}")]
+    PointCount {
+        master: usize,
+        contour: usize,
+        expected: usize,
+        found: usize,
+    },
+    #[error(
+        "master {master} has a {found:?} point at contour {contour}, point {point}, expected {expected:?}"
+    )]
+    PointType {
+        master: usize,
+        contour: usize,
+        point: usize,
+        expected: PointType,
+        found: PointType,
+    },
+    #[error("master {master} has {found} components, expected {expected}")]
+    ComponentCount {
+        master: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// Also reported for the same components in a different order.
+    #[error("master {master} uses '{found}' as component {component}, expected '{expected}'")]
+    ComponentBase {
+        master: usize,
+        component: usize,
+        expected: GlyphName,
+        found: GlyphName,
+    },
+    #[error("master {master} has {found} anchors, expected {expected}")]
+    AnchorCount {
+        master: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// Anchors are matched up by their order, so this is also reported for the same
+    /// anchors in a different order.
+    #[error(
+        "master {master} has {} as anchor {anchor}, expected {}",
+        anchor_name(.found),
+        anchor_name(.expected)
+    )]
+    AnchorName {
+        master: usize,
+        anchor: usize,
+        expected: Option<String>,
+        found: Option<String>,
+    },
+}
+
+fn anchor_name(name: &Option<String>) -> String {
+    match name {
+        Some(name) => format!("'{}'", name),
+        None => "an unnamed anchor".into(),
+    }
+}
+
+/// Check that all masters have the same point structure as the first: the same
+/// contours with the same point types, the same components in the same order, and
+/// the same anchors in the same order.
+///
+/// Returns the first difference, going through the masters in order and through
+/// contours, components and anchors in that order within each.
+pub fn check_compatibility<G: Borrow<Glyph>>(masters: &[G]) -> Result<(), Incompatibility> {
+    let (first, others) = match masters.split_first() {
+        Some((first, others)) => (first.borrow(), others),
+        None => return Ok(()),
+    };
+    for (master, glyph) in others.iter().enumerate() {
+        check_pair(first, glyph.borrow(), master + 1)?;
+    }
+    Ok(())
+}
+
+fn check_pair(expected: &Glyph, found: &Glyph, master: usize) -> Result<(), Incompatibility> {
+    if expected.contours.len() != found.contours.len() {
+        return Err(Incompatibility::ContourCount {
+            master,
+            expected: expected.contours.len(),
+            found: found.contours.len(),
+        });
+    }
+    for (contour, (a, b)) in expected.contours.iter().zip(&found.contours).enumerate() {
+        if a.points.len() != b.points.len() {
+            return Err(Incompatibility::PointCount {
+                master,
+                contour,
+                expected: a.points.len(),
+                found: b.points.len(),
+            });
+        }
+        for (point, (a, b)) in a.points.iter().zip(&b.points).enumerate() {
+            if a.typ != b.typ {
+                return Err(Incompatibility::PointType {
+                    master,
+                    contour,
+                    point,
+                    expected: a.typ,
+                    found: b.typ,
+                });
+            }
+        }
+    }
+
+    if expected.components.len() != found.components.len() {
+        return Err(Incompatibility::ComponentCount {
+            master,
+            expected: expected.components.len(),
+            found: found.components.len(),
+        });
+    }
+    for (component, (a, b)) in expected
+        .components
+        .iter()
+        .zip(&found.components)
+        .enumerate()
+    {
+        if a.base != b.base {
+            return Err(Incompatibility::ComponentBase {
+                master,
+                component,
+                expected: a.base.clone(),
+                found: b.base.clone(),
+            });
+        }
+    }
+
+    if expected.anchors.len() != found.anchors.len() {
+        return Err(Incompatibility::AnchorCount {
+            master,
+            expected: expected.anchors.len(),
+            found: found.anchors.len(),
+        });
+    }
+    for (anchor, (a, b)) in expected.anchors.iter().zip(&found.anchors).enumerate() {
+        if a.name != b.name {
+            return Err(Incompatibility::AnchorName {
+                master,
+                anchor,
+                expected: a.name.clone(),
+                found: b.name.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use norad::{AffineTransform, Anchor, Component};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test_util::contour;
+
+    fn master(types: &[&[PointType]], bases: &[&str], anchors: &[&str]) -> Glyph {
+        let mut glyph = Glyph::new_named("a");
+        glyph.contours = types
+            .iter()
+            .map(|types| {
+                let points: Vec<_> = types.iter().map(|&typ| (0.0, 0.0, typ)).collect();
+                contour(&points)
+            })
+            .collect();
+        glyph.components = bases
+            .iter()
+            .map(|&base| Component::new(base.into(), AffineTransform::default(), None, None))
+            .collect();
+        glyph.anchors = anchors
+            .iter()
+            .map(|&name| Anchor::new(0.0, 0.0, Some(name.into()), None, None, None))
+            .collect();
+        glyph
+    }
+
+    #[test]
+    fn compatible_masters() {
+        use PointType::*;
+
+        let light = master(&[&[Line, Line, Line]], &["acutecomb"], &["top"]);
+        let bold = light.clone();
+        assert_eq!(check_compatibility(&[&light, &bold, &light]), Ok(()));
+        assert_eq!(check_compatibility::<Glyph>(&[]), Ok(()));
+    }
+
+    #[test]
+    fn first_divergence() {
+        use PointType::*;
+
+        let light = master(
+            &[&[Line, Line], &[Curve, OffCurve, OffCurve]],
+            &["b", "c"],
+            &["top"],
+        );
+        let check = |other: Glyph| check_compatibility(&[light.clone(), light.clone(), other]);
+
+        assert_eq!(
+            check(master(&[&[Line, Line]], &[], &[])),
+            Err(Incompatibility::ContourCount {
+                master: 2,
+                expected: 2,
+                found: 1,
+            })
+        );
+        assert_eq!(
+            check(master(
+                &[&[Line, Line], &[Curve, OffCurve, Line]],
+                &["b", "c"],
+                &["top"]
+            )),
+            Err(Incompatibility::PointType {
+                master: 2,
+                contour: 1,
+                point: 2,
+                expected: OffCurve,
+                found: Line,
+            })
+        );
+        assert_eq!(
+            check(master(
+                &[&[Line, Line], &[Curve, OffCurve, OffCurve]],
+                &["c", "b"],
+                &[]
+            )),
+            Err(Incompatibility::ComponentBase {
+                master: 2,
+                component: 0,
+                expected: "b".into(),
+                found: "c".into(),
+            })
+        );
+        let error = check(master(
+            &[&[Line, Line], &[Curve, OffCurve, OffCurve]],
+            &["b", "c"],
+            &["bottom"],
+        ))
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "master 2 has 'bottom' as anchor 0, expected 'top'"
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Flattening components into contours.
+
+use norad::{AffineTransform, Contour, ContourPoint, Glyph, GlyphName, PointType};
+
+use crate::geometry::{apply, compose, is_mirroring};
+use crate::layer::cycle_error;
+use crate::{ComponentError, GlyphSource};
+
+/// The outline of `glyph` with all components replaced by the contours they stand for.
+///
+/// Components are resolved recursively through `layer`, with nested transforms
+/// combined. Contours drawn through a mirroring transform are reversed, so that they
+/// keep their direction. The glyph's own contours come first and are returned as they
+/// are; contours from components carry no identifiers, which would not be unique.
+///
+/// Errors when a component refers to a missing glyph or components form a cycle.
+pub fn decompose(glyph: &Glyph, layer: &impl GlyphSource) -> Result<Vec<Contour>, ComponentError> {
+    let mut contours = glyph.contours.clone();
+    let mut path = vec![glyph.name.clone()];
+    decompose_components(
+        glyph,
+        &AffineTransform::default(),
+        layer,
+        &mut path,
+        &mut contours,
+    )?;
+    Ok(contours)
+}
+
+fn decompose_components(
+    glyph: &Glyph,
+    transform: &AffineTransform,
+    layer: &impl GlyphSource,
+    path: &mut Vec<GlyphName>,
+    contours: &mut Vec<Contour>,
+) -> Result<(), ComponentError> {
+    for component in &glyph.components {
+        if let Some(error) = cycle_error(path, &component.base) {
+            return Err(error);
+        }
+        let base = layer
+            .get_glyph(&component.base)
+            .ok_or_else(|| ComponentError::MissingBase {
+                glyph: glyph.name.clone(),
+                base: component.base.clone(),
+            })?;
+
+        let transform = compose(transform, &component.transform);
+        for contour in &base.contours {
+            contours.push(transform_contour(contour, &transform));
+        }
+        path.push(base.name.clone());
+        decompose_components(base, &transform, layer, path, contours)?;
+        path.pop();
+    }
+    Ok(())
+}
+
+/// Transform a contour, dropping identifiers and reversing it if the transform mirrors.
+fn transform_contour(contour: &Contour, transform: &AffineTransform) -> Contour {
+    let points: Vec<ContourPoint> = contour
+        .points
+        .iter()
+        .map(|point| {
+            let (x, y) = apply(transform, (point.x, point.y));
+            ContourPoint::new(
+                x,
+                y,
+                point.typ,
+                point.smooth,
+                point.name.clone(),
+                None,
+                None,
+            )
+        })
+        .collect();
+    let points = if is_mirroring(transform) {
+        reverse_points(points)
+    } else {
+        points
+    };
+    Contour::new(points, None, None)
+}
+
+/// Reverse the direction of a contour's points.
+///
+/// A point's type describes the segment ending at it, so the types move along to
+/// the other end of their segments, as in fontTools' `ReverseContourPointPen`. The
+/// first point of a closed contour stays first.
+pub(crate) fn reverse_points(mut points: Vec<ContourPoint>) -> Vec<ContourPoint> {
+    if points.is_empty() {
+        return points;
+    }
+    let closed = points[0].typ != PointType::Move;
+    let mut last_segment_type = if closed {
+        // Rotate so that the first point ends up first again after reversing.
+        points.rotate_left(1);
+        points
+            .iter()
+            .find(|point| point.typ != PointType::OffCurve)
+            .map_or(PointType::OffCurve, |point| point.typ)
+    } else {
+        PointType::Move
+    };
+    points.reverse();
+    for point in &mut points {
+        if point.typ != PointType::OffCurve {
+            std::mem::swap(&mut point.typ, &mut last_segment_type);
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use norad::Component;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test_util::{self, contour, point};
+
+    fn positions(contour: &Contour) -> Vec<(f64, f64, PointType)> {
+        contour.points.iter().map(|p| (p.x, p.y, p.typ)).collect()
+    }
+
+    fn layer() -> HashMap<GlyphName, Glyph> {
+        let mut bar = Glyph::new_named("bar");
+        bar.contours = vec![contour(&[
+            (0.0, 0.0, PointType::Line),
+            (0.0, 100.0, PointType::Line),
+            (10.0, 100.0, PointType::Line),
+            (10.0, 0.0, PointType::Line),
+        ])];
+
+        let mut shifted = Glyph::new_named("shifted");
+        shifted.components = vec![Component::new(
+            "bar".into(),
+            AffineTransform {
+                x_offset: 50.0,
+                ..Default::default()
+            },
+            None,
+            None,
+        )];
+
+        let mut mirrored = Glyph::new_named("mirrored");
+        mirrored.components = vec![Component::new(
+            "shifted".into(),
+            AffineTransform {
+                x_scale: -1.0,
+                y_offset: 10.0,
+                ..Default::default()
+            },
+            None,
+            None,
+        )];
+
+        test_util::layer([bar, shifted, mirrored])
+    }
+
+    #[test]
+    fn nested_mirrored_components() {
+        let layer = layer();
+        let contours = decompose(&layer["mirrored"], &layer).unwrap();
+
+        assert_eq!(contours.len(), 1);
+        assert_eq!(
+            positions(&contours[0]),
+            vec![
+                (-50.0, 10.0, PointType::Line),
+                (-60.0, 10.0, PointType::Line),
+                (-60.0, 110.0, PointType::Line),
+                (-50.0, 110.0, PointType::Line),
+            ]
+        );
+    }
+
+    #[test]
+    fn reverse_curves() {
+        let closed = vec![
+            point(0.0, 0.0, PointType::Line),
+            point(1.0, 0.0, PointType::OffCurve),
+            point(2.0, 0.0, PointType::OffCurve),
+            point(3.0, 0.0, PointType::Curve),
+            point(4.0, 0.0, PointType::Line),
+        ];
+        assert_eq!(
+            positions(&Contour::new(reverse_points(closed), None, None)),
+            vec![
+                (0.0, 0.0, PointType::Curve),
+                (4.0, 0.0, PointType::Line),
+                (3.0, 0.0, PointType::Line),
+                (2.0, 0.0, PointType::OffCurve),
+                (1.0, 0.0, PointType::OffCurve),
+            ]
+        );
+
+        let open = vec![
+            point(0.0, 0.0, PointType::Move),
+            point(1.0, 0.0, PointType::OffCurve),
+            point(2.0, 0.0, PointType::QCurve),
+            point(3.0, 0.0, PointType::Line),
+        ];
+        assert_eq!(
+            positions(&Contour::new(reverse_points(open), None, None)),
+            vec![
+                (3.0, 0.0, PointType::Move),
+                (2.0, 0.0, PointType::Line),
+                (1.0, 0.0, PointType::OffCurve),
+                (0.0, 0.0, PointType::QCurve),
+            ]
+        );
+    }
+
+    #[test]
+    fn cycles_and_missing_glyphs() {
+        let mut layer = layer();
+        let bar = layer.get_mut("bar").unwrap();
+        bar.components = vec![Component::new(
+            "mirrored".into(),
+            AffineTransform::default(),
+            None,
+            None,
+        )];
+        assert_eq!(
+            decompose(&layer["mirrored"], &layer),
+            Err(ComponentError::Cycle(vec![
+                "mirrored".into(),
+                "shifted".into(),
+                "bar".into(),
+                "mirrored".into(),
+            ]))
+        );
+
+        layer.remove("shifted");
+        assert_eq!(
+            decompose(&layer["mirrored"], &layer),
+            Err(ComponentError::MissingBase {
+                glyph: "mirrored".into(),
+                base: "shifted".into(),
+            })
+        );
+    }
+}
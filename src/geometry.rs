@@ -0,0 +1,299 @@
+//! Geometry shared by the outline operations: affine transform arithmetic and the
+//! segments drawn by a contour.
+//!
+//! An [`AffineTransform`] maps `(x, y)` to `(x_scale * x + yx_scale * y + x_offset,
+//! xy_scale * x + y_scale * y + y_offset)`, as in fontTools.
+
+use norad::{AffineTransform, Contour, PointType};
+
+/// Apply `transform` to a point.
+pub(crate) fn apply(transform: &AffineTransform, (x, y): (f64, f64)) -> (f64, f64) {
+    (
+        transform.x_scale * x + transform.yx_scale * y + transform.x_offset,
+        transform.xy_scale * x + transform.y_scale * y + transform.y_offset,
+    )
+}
+
+/// The transform applying `inner` first, then `outer`.
+pub(crate) fn compose(outer: &AffineTransform, inner: &AffineTransform) -> AffineTransform {
+    let (x_offset, y_offset) = apply(outer, (inner.x_offset, inner.y_offset));
+    AffineTransform {
+        x_scale: outer.x_scale * inner.x_scale + outer.yx_scale * inner.xy_scale,
+        xy_scale: outer.xy_scale * inner.x_scale + outer.y_scale * inner.xy_scale,
+        yx_scale: outer.x_scale * inner.yx_scale + outer.yx_scale * inner.y_scale,
+        y_scale: outer.xy_scale * inner.yx_scale + outer.y_scale * inner.y_scale,
+        x_offset,
+        y_offset,
+    }
+}
+
+/// Whether `transform` mirrors, i.e. flips the direction of contours.
+pub(crate) fn is_mirroring(transform: &AffineTransform) -> bool {
+    transform.x_scale * transform.y_scale - transform.xy_scale * transform.yx_scale < 0.0
+}
+
+/// A line or Bézier curve drawn by a contour.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Segment {
+    Line([(f64, f64); 2]),
+    Quadratic([(f64, f64); 3]),
+    Cubic([(f64, f64); 4]),
+}
+
+impl Segment {
+    /// The first and last point.
+    pub(crate) fn ends(&self) -> ((f64, f64), (f64, f64)) {
+        match self {
+            Segment::Line([p0, p1]) => (*p0, *p1),
+            Segment::Quadratic([p0, _, p2]) => (*p0, *p2),
+            Segment::Cubic([p0, _, _, p3]) => (*p0, *p3),
+        }
+    }
+
+    /// The parameters in `(0, 1)` at which the segment turns around horizontally or
+    /// vertically, where it can reach beyond its ends.
+    pub(crate) fn extrema(&self) -> Vec<f64> {
+        let mut roots = Vec::new();
+        for axis in [0, 1] {
+            let c = |p: (f64, f64)| if axis == 0 { p.0 } else { p.1 };
+            match *self {
+                Segment::Line(_) => {}
+                Segment::Quadratic([p0, p1, p2]) => {
+                    let (p0, p1, p2) = (c(p0), c(p1), c(p2));
+                    let denominator = p0 - 2.0 * p1 + p2;
+                    if denominator != 0.0 {
+                        roots.push((p0 - p1) / denominator);
+                    }
+                }
+                Segment::Cubic([p0, p1, p2, p3]) => {
+                    let (p0, p1, p2, p3) = (c(p0), c(p1), c(p2), c(p3));
+                    // The derivative, divided by 3.
+                    let a = -p0 + 3.0 * p1 - 3.0 * p2 + p3;
+                    let b = 2.0 * (p0 - 2.0 * p1 + p2);
+                    let c = p1 - p0;
+                    solve_quadratic(a, b, c, &mut roots);
+                }
+            }
+        }
+        roots.retain(|t| *t > 0.0 && *t < 1.0);
+        roots
+    }
+
+    /// The point at parameter `t`.
+    pub(crate) fn point_at(&self, t: f64) -> (f64, f64) {
+        let lerp = |a: (f64, f64), b: (f64, f64)| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+        match *self {
+            Segment::Line([p0, p1]) => lerp(p0, p1),
+            Segment::Quadratic([p0, p1, p2]) => lerp(lerp(p0, p1), lerp(p1, p2)),
+            Segment::Cubic([p0, p1, p2, p3]) => {
+                let (q0, q1, q2) = (lerp(p0, p1), lerp(p1, p2), lerp(p2, p3));
+                lerp(lerp(q0, q1), lerp(q1, q2))
+            }
+        }
+    }
+}
+
+/// Push the real roots of `a * t² + b * t + c` onto `roots`.
+fn solve_quadratic(a: f64, b: f64, c: f64, roots: &mut Vec<f64>) {
+    if a.abs() < 1e-12 {
+        if b != 0.0 {
+            roots.push(-c / b);
+        }
+        return;
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant >= 0.0 {
+        let root = discriminant.sqrt();
+        roots.push((-b + root) / (2.0 * a));
+        roots.push((-b - root) / (2.0 * a));
+    }
+}
+
+/// The segments drawn by a contour, in order, split into plain lines and quadratic
+/// and cubic Béziers the way fontTools' pens split them.
+///
+/// A closed contour draws a segment to each on-curve point, including its first. An
+/// open contour starts at its first point. Quadratic runs with several off-curve
+/// points get their implied on-curve points between consecutive off-curves, and a
+/// contour made only of off-curve points is such a run going all the way around.
+pub(crate) fn segments(contour: &Contour) -> Vec<Segment> {
+    let points: Vec<((f64, f64), PointType)> =
+        contour.points.iter().map(|p| ((p.x, p.y), p.typ)).collect();
+    let mut segments = Vec::new();
+    let first_on_curve = match points
+        .iter()
+        .position(|(_, typ)| *typ != PointType::OffCurve)
+    {
+        Some(index) => index,
+        None => {
+            let off_curves: Vec<(f64, f64)> = points.iter().map(|(p, _)| *p).collect();
+            if let Some(&last) = off_curves.last() {
+                let start = midpoint(last, off_curves[0]);
+                push_quadratic_run(&mut segments, start, &off_curves, start);
+            }
+            return segments;
+        }
+    };
+
+    let closed = points[0].1 != PointType::Move;
+    let order: Vec<usize> = if closed {
+        (1..=points.len())
+            .map(|i| (first_on_curve + i) % points.len())
+            .collect()
+    } else {
+        (1..points.len()).collect()
+    };
+    let mut start = points[first_on_curve].0;
+    let mut off_curves = Vec::new();
+    for index in order {
+        let (point, typ) = points[index];
+        match typ {
+            PointType::OffCurve => {
+                off_curves.push(point);
+                continue;
+            }
+            PointType::QCurve => push_quadratic_run(&mut segments, start, &off_curves, point),
+            PointType::Curve => match off_curves.len() {
+                0 => segments.push(Segment::Line([start, point])),
+                1 => segments.push(Segment::Quadratic([start, off_curves[0], point])),
+                2 => segments.push(Segment::Cubic([start, off_curves[0], off_curves[1], point])),
+                _ => push_super_bezier(&mut segments, start, &off_curves, point),
+            },
+            PointType::Move | PointType::Line => segments.push(Segment::Line([start, point])),
+        }
+        off_curves.clear();
+        start = point;
+    }
+    segments
+}
+
+fn midpoint(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)
+}
+
+/// Split a run of quadratic off-curve points at their implied on-curve points.
+fn push_quadratic_run(
+    segments: &mut Vec<Segment>,
+    mut start: (f64, f64),
+    off_curves: &[(f64, f64)],
+    end: (f64, f64),
+) {
+    if off_curves.is_empty() {
+        segments.push(Segment::Line([start, end]));
+        return;
+    }
+    for (i, &control) in off_curves.iter().enumerate() {
+        let next = off_curves.get(i + 1).map_or(end, |&p| midpoint(control, p));
+        segments.push(Segment::Quadratic([start, control, next]));
+        start = next;
+    }
+}
+
+/// Split a cubic curve with more than two off-curve points into cubic Béziers, as
+/// fontTools' `decomposeSuperBezierSegment` does.
+fn push_super_bezier(
+    segments: &mut Vec<Segment>,
+    mut start: (f64, f64),
+    off_curves: &[(f64, f64)],
+    end: (f64, f64),
+) {
+    let n = off_curves.len();
+    let mut first_control = off_curves[0];
+    let mut second_control: Option<(f64, f64)> = None;
+    for i in 2..=n {
+        // Points in between the off-curve points.
+        let divisions = i.min(3).min(n - i + 2);
+        for j in 1..divisions {
+            let factor = j as f64 / divisions as f64;
+            let (a, b) = (off_curves[i - 2], off_curves[i - 1]);
+            let point = (a.0 + factor * (b.0 - a.0), a.1 + factor * (b.1 - a.1));
+            match second_control.take() {
+                None => second_control = Some(point),
+                Some(control) => {
+                    let on_curve = midpoint(control, point);
+                    segments.push(Segment::Cubic([start, first_control, control, on_curve]));
+                    start = on_curve;
+                    first_control = point;
+                }
+            }
+        }
+    }
+    segments.push(Segment::Cubic([
+        start,
+        first_control,
+        off_curves[n - 1],
+        end,
+    ]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::contour;
+
+    #[test]
+    fn composition() {
+        let scale = AffineTransform {
+            x_scale: 2.0,
+            y_scale: 3.0,
+            ..Default::default()
+        };
+        let shift = AffineTransform {
+            x_offset: 10.0,
+            y_offset: 20.0,
+            ..Default::default()
+        };
+        let rotate = AffineTransform {
+            x_scale: 0.0,
+            xy_scale: 1.0,
+            yx_scale: -1.0,
+            y_scale: 0.0,
+            ..Default::default()
+        };
+
+        for (outer, inner) in [
+            (scale, shift),
+            (shift, scale),
+            (rotate, shift),
+            (shift, rotate),
+        ] {
+            let point = (5.0, 7.0);
+            assert_eq!(
+                apply(&compose(&outer, &inner), point),
+                apply(&outer, apply(&inner, point))
+            );
+        }
+        assert_eq!(apply(&rotate, (1.0, 0.0)), (0.0, 1.0));
+        assert!(!is_mirroring(&rotate));
+        assert!(is_mirroring(&AffineTransform {
+            x_scale: -1.0,
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn contour_segments() {
+        use PointType::*;
+
+        let contour = contour(&[
+            (0.0, 0.0, Line),
+            (10.0, 0.0, OffCurve),
+            (20.0, 0.0, OffCurve),
+            (30.0, 0.0, QCurve),
+            (30.0, 10.0, OffCurve),
+            (30.0, 20.0, OffCurve),
+            (30.0, 30.0, OffCurve),
+            (0.0, 30.0, Curve),
+        ]);
+        assert_eq!(
+            segments(&contour),
+            vec![
+                Segment::Quadratic([(0.0, 0.0), (10.0, 0.0), (15.0, 0.0)]),
+                Segment::Quadratic([(15.0, 0.0), (20.0, 0.0), (30.0, 0.0)]),
+                Segment::Cubic([(30.0, 0.0), (30.0, 10.0), (30.0, 15.0), (30.0, 20.0)]),
+                Segment::Cubic([(30.0, 20.0), (30.0, 25.0), (30.0, 30.0), (0.0, 30.0)]),
+                Segment::Line([(0.0, 30.0), (0.0, 0.0)]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Interpolating glyphs from compatible masters.
+
+use std::borrow::Borrow;
+
+use norad::{AffineTransform, Glyph, Line};
+
+use crate::compatibility::{check_compatibility, Incompatibility};
+
+/// A reason masters could not be interpolated.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum InterpolationError {
+    #[error("no masters to interpolate")]
+    NoMasters,
+    #[error("{weights} weights given for {masters} masters")]
+    WeightCount { masters: usize, weights: usize },
+    #[error(transparent)]
+    Incompatible(#[from] Incompatibility),
+    #[error("master {master} has {found} guidelines, expected {expected}")]
+    GuidelineCount {
+        master: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A guideline is horizontal, vertical or angled in one master but not in another.
+    #[error("master {master} has a differently oriented guideline {guideline}")]
+    GuidelineKind { master: usize, guideline: usize },
+}
+
+/// Interpolate a glyph as the weighted sum of `masters`, one weight per master.
+///
+/// Contour points, component transforms, anchors, the advance and guidelines are
+/// interpolated; everything else, such as names, point types and the lib, is taken
+/// from the first master. Weights normally add up to 1, e.g. `[0.75, 0.25]` for the
+/// point a quarter of the way from the first master to the second. Guideline angles
+/// are interpolated the short way around the circle.
+///
+/// The masters have to [be compatible](check_compatibility) and have matching
+/// guidelines.
+pub fn interpolate<G: Borrow<Glyph>>(
+    masters: &[G],
+    weights: &[f64],
+) -> Result<Glyph, InterpolationError> {
+    let first = match masters.first() {
+        Some(first) => first.borrow(),
+        None => return Err(InterpolationError::NoMasters),
+    };
+    if weights.len() != masters.len() {
+        return Err(InterpolationError::WeightCount {
+            masters: masters.len(),
+            weights: weights.len(),
+        });
+    }
+    check_compatibility(masters)?;
+    check_guidelines(masters)?;
+
+    let blend = |value: &dyn Fn(&Glyph) -> f64| -> f64 {
+        masters
+            .iter()
+            .zip(weights)
+            .map(|(glyph, weight)| weight * value(glyph.borrow()))
+            .sum()
+    };
+
+    let mut glyph = first.clone();
+    glyph.width = blend(&|g| g.width);
+    glyph.height = blend(&|g| g.height);
+    for (c, contour) in glyph.contours.iter_mut().enumerate() {
+        for (p, point) in contour.points.iter_mut().enumerate() {
+            point.x = blend(&|g| g.contours[c].points[p].x);
+            point.y = blend(&|g| g.contours[c].points[p].y);
+        }
+    }
+    for (i, component) in glyph.components.iter_mut().enumerate() {
+        let transform = |g: &Glyph| g.components[i].transform;
+        component.transform = AffineTransform {
+            x_scale: blend(&|g| transform(g).x_scale),
+            xy_scale: blend(&|g| transform(g).xy_scale),
+            yx_scale: blend(&|g| transform(g).yx_scale),
+            y_scale: blend(&|g| transform(g).y_scale),
+            x_offset: blend(&|g| transform(g).x_offset),
+            y_offset: blend(&|g| transform(g).y_offset),
+        };
+    }
+    for (i, anchor) in glyph.anchors.iter_mut().enumerate() {
+        anchor.x = blend(&|g| g.anchors[i].x);
+        anchor.y = blend(&|g| g.anchors[i].y);
+    }
+    for (i, guideline) in glyph.guidelines.iter_mut().enumerate() {
+        guideline.line = match guideline.line {
+            Line::Horizontal(_) => Line::Horizontal(blend(&|g| match g.guidelines[i].line {
+                Line::Horizontal(y) => y,
+                _ => unreachable!("checked by check_guidelines"),
+            })),
+            Line::Vertical(_) => Line::Vertical(blend(&|g| match g.guidelines[i].line {
+                Line::Vertical(x) => x,
+                _ => unreachable!("checked by check_guidelines"),
+            })),
+            Line::Angle { degrees: first, .. } => {
+                let angle = |g: &Glyph| match g.guidelines[i].line {
+                    Line::Angle { x, y, degrees } => (x, y, degrees),
+                    _ => unreachable!("checked by check_guidelines"),
+                };
+                // Turn each angle into the one closest to the first master's, so
+                // that e.g. 350° and 10° meet at 0° rather than at 180°.
+                let degrees =
+                    blend(&|g| first + (angle(g).2 - first + 180.0).rem_euclid(360.0) - 180.0);
+                Line::Angle {
+                    x: blend(&|g| angle(g).0),
+                    y: blend(&|g| angle(g).1),
+                    degrees: degrees.rem_euclid(360.0),
+                }
+            }
+        };
+    }
+    Ok(glyph)
+}
+
+fn check_guidelines<G: Borrow<Glyph>>(masters: &[G]) -> Result<(), InterpolationError> {
+    let first = masters[0].borrow();
+    for (master, glyph) in masters.iter().enumerate().skip(1) {
+        let glyph = glyph.borrow();
+        if glyph.guidelines.len() != first.guidelines.len() {
+            return Err(InterpolationError::GuidelineCount {
+                master,
+                expected: first.guidelines.len(),
+                found: glyph.guidelines.len(),
+            });
+        }
+        for (guideline, (a, b)) in first.guidelines.iter().zip(&glyph.guidelines).enumerate() {
+            if std::mem::discriminant(&a.line) != std::mem::discriminant(&b.line) {
+                return Err(InterpolationError::GuidelineKind { master, guideline });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use norad::{Anchor, Component, Guideline, PointType};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test_util::contour;
+
+    fn master(scale: f64, angle: f64) -> Glyph {
+        let mut glyph = Glyph::new_named("a");
+        glyph.width = 500.0 * scale;
+        glyph.contours = vec![contour(&[
+            (0.0, 0.0, PointType::Line),
+            (100.0 * scale, 40.0, PointType::Line),
+        ])];
+        glyph.components = vec![Component::new(
+            "b".into(),
+            AffineTransform {
+                x_scale: scale,
+                ..Default::default()
+            },
+            None,
+            None,
+        )];
+        glyph.anchors = vec![Anchor::new(
+            200.0 * scale,
+            0.0,
+            Some("top".into()),
+            None,
+            None,
+            None,
+        )];
+        glyph.guidelines = vec![
+            Guideline::new(Line::Horizontal(10.0 * scale), None, None, None, None),
+            Guideline::new(
+                Line::Angle {
+                    x: 0.0,
+                    y: 0.0,
+                    degrees: angle,
+                },
+                None,
+                None,
+                None,
+                None,
+            ),
+        ];
+        glyph
+    }
+
+    #[test]
+    fn interpolate_masters() {
+        let glyph = interpolate(&[master(1.0, 350.0), master(3.0, 30.0)], &[0.75, 0.25]).unwrap();
+
+        assert_eq!(glyph.width, 750.0);
+        let points: Vec<_> = glyph.contours[0]
+            .points
+            .iter()
+            .map(|p| (p.x, p.y))
+            .collect();
+        assert_eq!(points, vec![(0.0, 0.0), (150.0, 40.0)]);
+        assert_eq!(glyph.components[0].transform.x_scale, 1.5);
+        assert_eq!(glyph.components[0].transform.y_scale, 1.0);
+        assert_eq!((glyph.anchors[0].x, glyph.anchors[0].y), (300.0, 0.0));
+        assert_eq!(glyph.guidelines[0].line, Line::Horizontal(15.0));
+        assert_eq!(
+            glyph.guidelines[1].line,
+            Line::Angle {
+                x: 0.0,
+                y: 0.0,
+                degrees: 0.0,
+            }
+        );
+
+        let three = [master(1.0, 90.0), master(2.0, 90.0), master(3.0, 90.0)];
+        let glyph = interpolate(&three, &[0.5, 0.0, 0.5]).unwrap();
+        assert_eq!(glyph.width, 1000.0);
+    }
+
+    #[test]
+    fn interpolation_errors() {
+        let light = master(1.0, 0.0);
+        assert_eq!(
+            interpolate::<Glyph>(&[], &[]),
+            Err(InterpolationError::NoMasters)
+        );
+        assert_eq!(
+            interpolate(&[&light, &light], &[1.0]),
+            Err(InterpolationError::WeightCount {
+                masters: 2,
+                weights: 1,
+            })
+        );
+
+        let mut bold = master(2.0, 0.0);
+        bold.anchors.clear();
+        assert_eq!(
+            interpolate(&[&light, &bold], &[0.5, 0.5]),
+            Err(InterpolationError::Incompatible(
+                Incompatibility::AnchorCount {
+                    master: 1,
+                    expected: 1,
+                    found: 0,
+                }
+            ))
+        );
+
+        let mut bold = master(2.0, 0.0);
+        bold.guidelines[0].line = Line::Vertical(20.0);
+        assert_eq!(
+            interpolate(&[&light, &bold], &[0.5, 0.5]),
+            Err(InterpolationError::GuidelineKind {
+                master: 1,
+                guideline: 0,
+            })
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! Operations on a whole layer of parsed glyphs.
+//!
+//! Components refer to their base glyph by name, so anything that follows them needs
+//! the other glyphs of the layer. A [`GlyphSource`] provides them.
+
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    hash::BuildHasher,
+};
+
+use norad::{Glyph, GlyphName, Layer};
+
+/// The glyphs of a layer, looked up by name.
+///
+/// Implemented for norad's [`Layer`] and for maps from glyph names to glyphs, e.g. as
+/// built from the results of [`crate::parse_glif`].
+pub trait GlyphSource {
+    /// The glyph named `name`, if the layer has one.
+    fn get_glyph(&self, name: &str) -> Option<&Glyph>;
+
+    /// All glyphs of the layer, in no particular order.
+    fn iter_glyphs(&self) -> Box<dyn Iterator<Item = &Glyph> + '_>;
+}
+
+impl<G: Borrow<Glyph>, S: BuildHasher> GlyphSource for HashMap<GlyphName, G, S> {
+    fn get_glyph(&self, name: &str) -> Option<&Glyph> {
+        self.get(name).map(Borrow::borrow)
+    }
+
+    fn iter_glyphs(&self) -> Box<dyn Iterator<Item = &Glyph> + '_> {
+        Box::new(self.values().map(Borrow::borrow))
+    }
+}
+
+impl<G: Borrow<Glyph>> GlyphSource for BTreeMap<GlyphName, G> {
+    fn get_glyph(&self, name: &str) -> Option<&Glyph> {
+        self.get(name).map(Borrow::borrow)
+    }
+
+    fn iter_glyphs(&self) -> Box<dyn Iterator<Item = &Glyph> + '_> {
+        Box::new(self.values().map(Borrow::borrow))
+    }
+}
+
+impl GlyphSource for Layer {
+    fn get_glyph(&self, name: &str) -> Option<&Glyph> {
+        Layer::get_glyph(self, name).map(|glyph| &**glyph)
+    }
+
+    fn iter_glyphs(&self) -> Box<dyn Iterator<Item = &Glyph> + '_> {
+        Box::new(self.iter().map(|glyph| &**glyph))
+    }
+}
+
+/// A problem with the component references of a layer.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ComponentError {
+    #[error("glyph '{glyph}' uses the missing glyph '{base}' as a component")]
+    MissingBase { glyph: GlyphName, base: GlyphName },
+    #[error("glyph '{0}' uses itself as a component")]
+    SelfReference(GlyphName),
+    /// Glyphs using each other as components, starting and ending with the same glyph.
+    #[error("component cycle {}", .0.join(" → "))]
+    Cycle(Vec<GlyphName>),
+}
+
+impl ComponentError {
+    /// The glyph the problem is reported for: the glyph using a missing base or
+    /// itself, or the glyph a cycle starts with.
+    pub fn glyph(&self) -> &GlyphName {
+        match self {
+            ComponentError::MissingBase { glyph, .. } => glyph,
+            ComponentError::SelfReference(glyph) => glyph,
+            ComponentError::Cycle(chain) => &chain[0],
+        }
+    }
+}
+
+/// Check that every component in the layer refers to an existing glyph other than
+/// its own, and that no glyphs use each other as components in a cycle.
+///
+/// Returns all problems found, in the order of the glyph names they concern.
+pub fn validate_components(layer: &impl GlyphSource) -> Vec<ComponentError> {
+    let graph = ComponentGraph::new(layer);
+    let mut errors = Vec::new();
+    for (glyph, bases) in &graph.components {
+        for base in bases {
+            if !graph.components.contains_key(base) {
+                errors.push(ComponentError::MissingBase {
+                    glyph: glyph.clone(),
+                    base: base.clone(),
+                });
+            }
+        }
+    }
+    errors.extend(graph.cycles());
+    errors.sort_by(|a, b| a.glyph().cmp(b.glyph()));
+    errors
+}
+
+/// The error for the last glyph of `path` using `base` as a component, if `base` is
+/// on the path already and the component closes a cycle.
+pub(crate) fn cycle_error<N: Borrow<GlyphName>>(
+    path: &[N],
+    base: &GlyphName,
+) -> Option<ComponentError> {
+    let start = path.iter().position(|name| name.borrow() == base)?;
+    if start == path.len() - 1 {
+        return Some(ComponentError::SelfReference(base.clone()));
+    }
+    let mut chain: Vec<GlyphName> = path[start..].iter().map(|n| n.borrow().clone()).collect();
+    chain.push(base.clone());
+    Some(ComponentError::Cycle(chain))
+}
+
+/// Which glyphs of a layer use which others as components.
+///
+/// Built once from a layer, the graph answers the questions of incremental builds:
+/// what a glyph is built from, what has to be rebuilt when it changes, and in which
+/// order glyphs can be processed so that bases come before the composites using them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComponentGraph {
+    /// The distinct bases of each glyph in the layer, in order of first use.
+    components: BTreeMap<GlyphName, Vec<GlyphName>>,
+    /// The glyphs directly using each base, including bases missing from the layer.
+    used_by: BTreeMap<GlyphName, BTreeSet<GlyphName>>,
+}
+
+impl ComponentGraph {
+    /// Build the graph of all component references in `layer`.
+    pub fn new(layer: &impl GlyphSource) -> Self {
+        let mut graph = ComponentGraph::default();
+        for glyph in layer.iter_glyphs() {
+            let mut bases: Vec<GlyphName> = Vec::new();
+            for component in &glyph.components {
+                if !bases.contains(&component.base) {
+                    bases.push(component.base.clone());
+                }
+                graph
+                    .used_by
+                    .entry(component.base.clone())
+                    .or_default()
+                    .insert(glyph.name.clone());
+            }
+            graph.components.insert(glyph.name.clone(), bases);
+        }
+        graph
+    }
+
+    /// The glyphs `name` directly uses as components, in order of first use.
+    ///
+    /// Empty for glyphs without components and glyphs not in the layer.
+    pub fn components_of(&self, name: &str) -> &[GlyphName] {
+        self.components.get(name).map_or(&[], Vec::as_slice)
+    }
+
+    /// The glyphs directly using `name` as a component, sorted by name.
+    pub fn used_by(&self, name: &str) -> impl Iterator<Item = &GlyphName> + '_ {
+        self.used_by.get(name).into_iter().flatten()
+    }
+
+    /// All glyphs using `name` as a component, directly or through other composites:
+    /// the glyphs to rebuild when `name` changes.
+    ///
+    /// Does not include `name` itself, unless it is part of a component cycle.
+    pub fn all_used_by(&self, name: &str) -> BTreeSet<GlyphName> {
+        let mut found = BTreeSet::new();
+        let mut stack: Vec<&GlyphName> = self.used_by(name).collect();
+        while let Some(user) = stack.pop() {
+            if found.insert(user.clone()) {
+                stack.extend(self.used_by(user));
+            }
+        }
+        found
+    }
+
+    /// The glyphs of the layer ordered so that every glyph comes after the glyphs it
+    /// uses as components. Glyphs that are equally ready are ordered by name.
+    ///
+    /// Missing bases are ignored. Errors when glyphs use each other in a cycle.
+    pub fn topological_order(&self) -> Result<Vec<GlyphName>, ComponentError> {
+        Ok(self.depths()?.into_iter().map(|(name, _)| name).collect())
+    }
+
+    /// The component depth of each glyph of the layer, in topological order: 0 for
+    /// glyphs without components in the layer, otherwise one more than the deepest
+    /// of its bases.
+    ///
+    /// Missing bases are ignored. Errors when glyphs use each other in a cycle.
+    pub fn depths(&self) -> Result<Vec<(GlyphName, usize)>, ComponentError> {
+        // Kahn's algorithm, processing glyphs level by level.
+        let mut pending: BTreeMap<&GlyphName, usize> = self
+            .components
+            .iter()
+            .map(|(name, bases)| {
+                let in_layer = bases.iter().filter(|b| self.components.contains_key(*b));
+                (name, in_layer.count())
+            })
+            .collect();
+        let mut level: Vec<&GlyphName> = pending
+            .iter()
+            .filter(|(_, &count)| count == 0)
+            .map(|(&name, _)| name)
+            .collect();
+        let mut order = Vec::with_capacity(pending.len());
+        let mut depth = 0;
+        while !level.is_empty() {
+            let mut next = Vec::new();
+            for name in level {
+                pending.remove(name);
+                order.push((name.clone(), depth));
+                for user in self.used_by(name) {
+                    let count = pending.get_mut(user).expect("users are in the layer");
+                    *count -= 1;
+                    if *count == 0 {
+                        next.push(user);
+                    }
+                }
+            }
+            next.sort();
+            level = next;
+            depth += 1;
+        }
+
+        if pending.is_empty() {
+            Ok(order)
+        } else {
+            Err(self.cycles().swap_remove(0))
+        }
+    }
+
+    /// The component cycles of the layer, self-references included, found by a
+    /// depth-first search through the glyphs in name order. A cycle is reported for
+    /// every component leading back into the search path, starting at the glyph it
+    /// leads back to.
+    fn cycles(&self) -> Vec<ComponentError> {
+        let mut errors = Vec::new();
+        let mut done: HashSet<&GlyphName> = HashSet::new();
+        for start in self.components.keys() {
+            if done.contains(start) {
+                continue;
+            }
+            let mut path: Vec<&GlyphName> = vec![start];
+            let mut next_base: Vec<usize> = vec![0];
+            while let Some(&current) = path.last() {
+                let index = next_base.last_mut().unwrap();
+                let base = match self.components_of(current).get(*index) {
+                    Some(base) => base,
+                    None => {
+                        done.insert(current);
+                        path.pop();
+                        next_base.pop();
+                        continue;
+                    }
+                };
+                *index += 1;
+                if done.contains(base) || !self.components.contains_key(base) {
+                    continue;
+                }
+                match cycle_error(&path, base) {
+                    Some(error) => errors.push(error),
+                    None => {
+                        path.push(base);
+                        next_base.push(0);
+                    }
+                }
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use norad::{AffineTransform, Component};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test_util::layer;
+
+    fn composite(name: &str, bases: &[&str]) -> Glyph {
+        let mut glyph = Glyph::new_named(name);
+        glyph.components = bases
+            .iter()
+            .map(|&base| Component::new(base.into(), AffineTransform::default(), None, None))
+            .collect();
+        glyph
+    }
+
+    fn names<'a>(names: impl IntoIterator<Item = &'a GlyphName>) -> Vec<&'a str> {
+        names.into_iter().map(|name| &**name).collect()
+    }
+
+    #[test]
+    fn valid_components() {
+        let glyphs = vec![
+            composite("A", &[]),
+            composite("acutecomb", &[]),
+            composite("Aacute", &["A", "acutecomb"]),
+            composite("Aacute.alt", &["Aacute"]),
+        ];
+        let mut norad_layer = Layer::default();
+        for glyph in &glyphs {
+            norad_layer.insert_glyph(glyph.clone());
+        }
+        assert_eq!(validate_components(&layer(glyphs)), Vec::new());
+        assert_eq!(validate_components(&norad_layer), Vec::new());
+    }
+
+    #[test]
+    fn invalid_components() {
+        let layer = layer(vec![
+            composite("A", &["B"]),
+            composite("B", &["C", "missing"]),
+            composite("C", &["A"]),
+            composite("D", &["D"]),
+            composite("E", &["A"]),
+        ]);
+        assert_eq!(
+            validate_components(&layer),
+            vec![
+                ComponentError::Cycle(vec!["A".into(), "B".into(), "C".into(), "A".into()]),
+                ComponentError::MissingBase {
+                    glyph: "B".into(),
+                    base: "missing".into(),
+                },
+                ComponentError::SelfReference("D".into()),
+            ]
+        );
+        assert_eq!(
+            validate_components(&layer)[0].to_string(),
+            "component cycle A → B → C → A"
+        );
+    }
+
+    #[test]
+    fn component_graph() {
+        let layer = layer(vec![
+            composite("A", &[]),
+            composite("acutecomb", &[]),
+            composite("Aacute", &["A", "acutecomb", "A"]),
+            composite("Aacute.alt", &["Aacute", "missing"]),
+            composite("Aring", &["A"]),
+        ]);
+        let graph = ComponentGraph::new(&layer);
+
+        assert_eq!(names(graph.components_of("Aacute")), ["A", "acutecomb"]);
+        assert_eq!(names(graph.components_of("A")), Vec::<&str>::new());
+        assert_eq!(names(graph.used_by("A")), ["Aacute", "Aring"]);
+        assert_eq!(names(graph.used_by("missing")), ["Aacute.alt"]);
+        assert_eq!(
+            names(&graph.all_used_by("A")),
+            ["Aacute", "Aacute.alt", "Aring"]
+        );
+        assert_eq!(
+            graph.depths().unwrap(),
+            vec![
+                ("A".into(), 0),
+                ("acutecomb".into(), 0),
+                ("Aacute".into(), 1),
+                ("Aring".into(), 1),
+                ("Aacute.alt".into(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn component_graph_cycles() {
+        let graph = ComponentGraph::new(&layer(vec![
+            composite("A", &["B"]),
+            composite("B", &["C"]),
+            composite("C", &["B"]),
+            composite("D", &["A"]),
+        ]));
+        assert_eq!(
+            graph.topological_order(),
+            Err(ComponentError::Cycle(vec![
+                "B".into(),
+                "C".into(),
+                "B".into()
+            ]))
+        );
+        assert_eq!(names(&graph.all_used_by("B")), ["A", "B", "C", "D"]);
+
+        let graph = ComponentGraph::new(&layer(vec![composite("A", &["A"])]));
+        assert_eq!(
+            graph.topological_order(),
+            Err(ComponentError::SelfReference("A".into()))
+        );
+    }
+}
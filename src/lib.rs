@@ -1,16 +1,53 @@
-use std::{collections::HashSet, path::PathBuf};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
 
 use norad::{
-    AffineTransform, Anchor, Color, GlifVersion, Glyph, Guideline, Identifier, Image, Line, Plist,
+    AffineTransform, Color, GlifVersion, Glyph, GlyphName, Identifier, Image, Line, PointType,
 };
 use quick_xml::{
-    events::{attributes::Attributes, Event},
+    events::{attributes::Attributes, BytesDecl, BytesStart, Event},
     Reader,
 };
 
-// use builder::OutlineBuilder;
+#[cfg(feature = "arena")]
+pub use arena::{
+    parse_glif_in, parse_glif_in_with_options, ArenaComponent, ArenaContour, ArenaGlyph, ArenaPoint,
+};
+pub use borrowed::{
+    parse_glif_ref, parse_glif_ref_with_options, AnchorRef, ComponentRef, ContourRef,
+    DictionaryRef, GlyphRef, GuidelineRef, ImageRef, PointRef, RawLib, ValueRef,
+};
+pub use bounds::{contour_bounds, glyph_bounds, sidebearings, Rect, Sidebearings};
+pub use builder::{GlyphBuilder, OutlineBuilder, OutlineError};
+pub use compatibility::{check_compatibility, Incompatibility};
+pub use decompose::decompose;
+pub use interpolate::{interpolate, InterpolationError};
+pub use layer::{validate_components, ComponentError, ComponentGraph, GlyphSource};
+pub use lint::{lint_glyph, Finding, LintOptions, Rule, Severity};
+pub use transform::transform_glyph;
+pub use visitor::GlifVisitor;
+use visitor::GlyphCollector;
 
-// pub mod builder;
+#[cfg(feature = "arena")]
+mod arena;
+mod borrowed;
+mod bounds;
+mod builder;
+mod compatibility;
+mod decompose;
+mod geometry;
+mod interpolate;
+mod layer;
+mod lint;
+#[cfg(test)]
+mod test_util;
+mod transform;
+mod visitor;
 
 #[derive(Debug, thiserror::Error)]
 #[non_exhaustive]
@@ -19,6 +56,8 @@ pub enum Error {
     Xml(#[source] quick_xml::Error),
     #[error("failed to parse the glif file: {0}")]
     Parse(ErrorKind),
+    #[error("failed to read '{}'", .0.display())]
+    Io(PathBuf, #[source] std::io::Error),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -27,203 +66,699 @@ pub enum ErrorKind {
     BadIdentifier,
     #[error("found duplicate element")]
     DuplicateElement,
+    #[error("duplicate codepoint U+{:04X}", *.0 as u32)]
+    DuplicateCodepoint(char),
     #[error("duplicate identifier")]
     DuplicateIdentifier,
+    #[error("DOCTYPE declarations are not allowed")]
+    DocTypeNotAllowed,
     #[error("invalid anchor element")]
     InvalidAnchor,
     #[error("an angle must be between 0 and 360°")]
     InvalidAngle,
+    #[error("codepoint '{0}' is out of range")]
+    CodepointOutOfRange(String),
     #[error("invalid codepoint '{0}': {1}")]
     InvalidCodepoint(String, Box<dyn std::error::Error>),
     #[error("invalid color attribute")]
     InvalidColor,
+    #[error("invalid component element")]
+    InvalidComponent,
     #[error("invalid glyph element")]
     InvalidGlyph,
     #[error("invalid guideline element")]
     InvalidGuideline,
     #[error("invalid image element")]
     InvalidImage,
+    #[error("image file name '{0}' must be a bare file name")]
+    InvalidImageFileName(String),
+    #[error("image file '{}' does not exist", .0.display())]
+    ImageFileMissing(PathBuf),
+    #[error("image file '{}' is not a PNG file", .0.display())]
+    ImageNotPng(PathBuf),
     #[error("invalid number '{0}': {1}")]
     InvalidInteger(String, std::num::ParseIntError),
+    #[error("a note must only contain text")]
+    InvalidNote,
     #[error("invalid number '{0}': {1}")]
     InvalidNumber(String, std::num::ParseFloatError),
+    #[error("invalid point element")]
+    InvalidPoint,
+    #[error("the glif file is not valid UTF-8 (invalid byte at offset {0})")]
+    InvalidUtf8(usize),
     #[error("unvalid unicode element")]
     InvalidUnicode,
+    #[error("limit exceeded: {what} must not exceed {limit}")]
+    LimitExceeded { what: &'static str, limit: usize },
     #[error("the glyph lib must be a dictionary")]
     LibMustBeDictionary,
+    #[error("malformed hexadecimal codepoint '{0}'")]
+    MalformedHex(String),
+    #[error("malformed number '{0}'")]
+    MalformedNumber(String),
+    #[error("the XML declaration must come first in the file")]
+    MisplacedDeclaration,
+    #[error("number '{0}' is out of range")]
+    NumberOutOfRange(String),
+    #[error("processing instructions are not allowed")]
+    ProcessingInstructionNotAllowed,
     #[error("failed to parse glyph lib")]
     ParsePlist(#[source] Box<dyn std::error::Error>),
     #[error("expected a single 'glyph' element in the glif file")]
     TrailingData,
     #[error("unexpected attribute")]
     UnexpectedAttribute,
+    #[error("unexpected element '{0}'")]
+    UnexpectedElement(String),
     #[error("unexpected end of file")]
     UnexpectedEof,
+    #[error("unexpected text outside of a note")]
+    UnexpectedText,
+    #[error("unknown entity '&{0};'")]
+    UnknownEntity(String),
+    #[error("invalid outline: {0}")]
+    Outline(#[from] OutlineError),
+    #[error("unsupported encoding '{0}', glif files must be UTF-8")]
+    UnsupportedEncoding(String),
     #[error("unsupported glif version")]
     UnsupportedGlifVersion,
+    #[error("unsupported XML version '{0}'")]
+    UnsupportedXmlVersion(String),
     #[error("'glyph' must be the first element in a glif file")]
     WrongFirstElement,
 }
 
+/// How to deal with problems in a glif file that can be recovered from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Treat every problem as an error.
+    #[default]
+    Strict,
+    /// Repair recoverable problems and report them as [`Warning`]s.
+    Lenient,
+}
+
+/// A recoverable problem that was repaired while parsing in [`Strictness::Lenient`] mode,
+/// or content that is valid but was not kept.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A codepoint was listed more than once; only the first occurrence was kept.
+    DuplicateCodepoint(char),
+    /// A contour without points was dropped, in either mode. `contour` is its index
+    /// among all contours of the outline, empty ones included.
+    EmptyContour {
+        contour: usize,
+        identifier: Option<Identifier>,
+    },
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::DuplicateCodepoint(c) => {
+                write!(f, "dropped duplicate codepoint U+{:04X}", *c as u32)
+            }
+            Warning::EmptyContour {
+                contour,
+                identifier: None,
+            } => write!(f, "dropped empty contour {}", contour),
+            Warning::EmptyContour {
+                contour,
+                identifier: Some(identifier),
+            } => write!(
+                f,
+                "dropped empty contour {} with identifier '{}'",
+                contour,
+                identifier.as_str()
+            ),
+        }
+    }
+}
+
+/// Upper bounds on the resources a single glif file may consume while parsing.
+///
+/// The defaults are generous enough for any real-world glyph, but keep a hostile
+/// file from exhausting memory or stack. Exceeding a limit is reported as
+/// [`ErrorKind::LimitExceeded`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum size of the glif file in bytes.
+    pub max_input_size: usize,
+    /// Maximum length in bytes of an attribute value, the note or a string in the lib.
+    pub max_string_length: usize,
+    /// Maximum number of `<unicode>` elements.
+    pub max_codepoints: usize,
+    /// Maximum number of `<anchor>` elements.
+    pub max_anchors: usize,
+    /// Maximum number of `<guideline>` elements.
+    pub max_guidelines: usize,
+    /// Maximum number of `<contour>` elements.
+    pub max_contours: usize,
+    /// Maximum number of `<point>` elements, across all contours.
+    pub max_points: usize,
+    /// Maximum number of `<component>` elements.
+    pub max_components: usize,
+    /// Maximum nesting depth of elements inside the `<lib>`, e.g. `<dict>`s in `<array>`s.
+    pub max_lib_depth: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_input_size: 64 * 1024 * 1024,
+            max_string_length: 1024 * 1024,
+            max_codepoints: 1024,
+            max_anchors: 1024,
+            max_guidelines: 1024,
+            max_contours: 16 * 1024,
+            max_points: 1024 * 1024,
+            max_components: 16 * 1024,
+            max_lib_depth: 128,
+        }
+    }
+}
+
+/// Options controlling how a glif file is parsed.
+///
+/// The `skip_*` flags let callers that only need part of a glyph, e.g. its name and
+/// codepoints for a cmap, fast-forward over the elements they don't care about. Skipped
+/// elements are left at their default in the returned glyph and their content is not
+/// validated, but they may still only appear once.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// How to deal with recoverable problems.
+    pub strictness: Strictness,
+    /// Resource limits for untrusted input.
+    pub limits: Limits,
+    /// Don't parse the `<lib>` element.
+    pub skip_lib: bool,
+    /// Don't parse the `<note>` element.
+    pub skip_note: bool,
+    /// Don't parse the `<image>` element.
+    pub skip_image: bool,
+    /// Don't parse `<anchor>` and `<guideline>` elements.
+    pub skip_anchors_and_guidelines: bool,
+    /// Don't parse the `<outline>` element.
+    pub skip_outline: bool,
+}
+
+/// Parse a glif file, treating every problem as an error.
+///
+/// # Untrusted input
+///
+/// The parser never expands entities other than the five predefined ones (`&lt;`,
+/// `&gt;`, `&amp;`, `&apos;`, `&quot;`) and character references, and never fetches
+/// external resources. DOCTYPE declarations, and with them any custom or external
+/// entity definitions, are rejected, as are processing instructions and references
+/// to unknown entities. So are elements and text the specification doesn't allow,
+/// rather than being skipped over unchecked.
+///
+/// Resource usage is bounded by the default [`Limits`].
+///
+/// Empty contours are dropped silently; use [`parse_glif_with_options`] to be told
+/// about them.
 pub fn parse_glif(xml: &[u8]) -> Result<Glyph, Error> {
+    parse_glif_with_options(xml, &ParseOptions::default()).map(|(glyph, _)| glyph)
+}
+
+/// Parse a glif file, repairing recoverable problems and returning them as warnings
+/// alongside the glyph.
+pub fn parse_glif_lenient(xml: &[u8]) -> Result<(Glyph, Vec<Warning>), Error> {
+    let options = ParseOptions {
+        strictness: Strictness::Lenient,
+        ..Default::default()
+    };
+    parse_glif_with_options(xml, &options)
+}
+
+/// Parse a glif file according to the given [`ParseOptions`].
+///
+/// Besides the problems repaired in [`Strictness::Lenient`] mode, the warnings report
+/// empty contours, which are dropped in either mode.
+pub fn parse_glif_with_options(
+    xml: &[u8],
+    options: &ParseOptions,
+) -> Result<(Glyph, Vec<Warning>), Error> {
+    let mut collector = GlyphCollector::new();
+    let mut warnings = visit_glif(xml, options, &mut collector)?;
+    let (glyph, outline_warnings) = collector.finish()?;
+    warnings.extend(outline_warnings);
+    Ok((glyph, warnings))
+}
+
+/// Parse a glif file and report its content to a [`GlifVisitor`] as it is read,
+/// without building a [`Glyph`].
+///
+/// The structure of the file, identifier uniqueness and resource limits are
+/// validated as in [`parse_glif_with_options`]. The point sequence of contours is
+/// not; that is up to the visitor. Elements skipped by the options are not reported.
+/// Returns the warnings collected in [`Strictness::Lenient`] mode.
+pub fn visit_glif<'a, V: GlifVisitor<'a>>(
+    xml: &'a [u8],
+    options: &ParseOptions,
+    visitor: &mut V,
+) -> Result<Vec<Warning>, Error> {
+    #[derive(Clone, Copy)]
     enum State {
         /// At the start of the glif buffer.
         Start,
         /// Inside the <glyph> element.
-        Glyph(Glyph),
-        // /// Inside the <outline> element.
-        // Outline(Glyph, OutlineBuilder),
-        // /// Inside the <contour> element.
-        // Contour(Glyph, OutlineBuilder),
+        Glyph,
+        /// Inside the <outline> element.
+        Outline,
+        /// Inside a <contour> element.
+        Contour,
         /// Done with <glyph> and expecting the end of the file.
-        Done(Glyph),
+        Done,
     }
 
+    let limits = &options.limits;
+    check_limit("input size", xml.len(), limits.max_input_size)?;
+    let xml = check_encoding(xml)?;
     let mut reader = Reader::from_reader(xml);
     reader.trim_text(true);
     let mut state = State::Start;
     let mut buf = Vec::with_capacity(xml.len());
+    let mut format = GlifVersion::V2;
     let mut identifier_set: HashSet<Identifier> = HashSet::new();
+    let mut codepoints: Vec<char> = Vec::new();
+    let mut counts = ElementCounts::default();
     let mut seen_advance = false; // TODO: integrate seen_* into state above?
     let mut seen_lib = false;
-    // let mut seen_outline = false;
+    let mut seen_note = false;
+    let mut seen_image = false;
+    let mut seen_outline = false;
+    let mut warnings = Vec::new();
 
     // TODO: deal with unexpected elements in v1
     loop {
         state = match (state, reader.read_event(&mut buf).map_err(Error::Xml)?) {
             (state, Event::Comment(_)) => state,
-            (state, Event::Decl(_)) => state,
+            (_, Event::DocType(_)) => return Err(Error::Parse(ErrorKind::DocTypeNotAllowed)),
+            (_, Event::PI(_)) => {
+                return Err(Error::Parse(ErrorKind::ProcessingInstructionNotAllowed))
+            }
+            (State::Start, Event::Decl(e)) => {
+                check_declaration(&e)?;
+                State::Start
+            }
 
             // The first and only element must be a <glyph>.
             (State::Start, Event::Start(e)) if e.name() == b"glyph" => {
-                let glyph = parse_glyph(&reader, e.attributes())?;
-                State::Glyph(glyph)
+                let attributes = input_attributes(xml, &reader, &e, false);
+                let (name, glyph_format, format_minor) = parse_glyph(attributes, limits)?;
+                format = glyph_format;
+                visitor.glyph(name, format, format_minor)?;
+                State::Glyph
             }
             (State::Start, Event::Empty(_) | Event::Start(_)) => {
                 return Err(Error::Parse(ErrorKind::WrongFirstElement))
             }
 
             // Handle immediate child elements of <glyph>.
-            (State::Glyph(mut glyph), Event::Empty(e)) if e.name() == b"unicode" => {
-                let codepoint = parse_unicode(&reader, e.attributes())?;
-                glyph.codepoints.push(codepoint);
-                State::Glyph(glyph)
-            }
-            (State::Glyph(mut glyph), Event::Empty(e)) if e.name() == b"anchor" => {
-                let anchor =
-                    parse_anchor(&reader, e.attributes(), &mut identifier_set, &glyph.format)?;
-                glyph.anchors.push(anchor);
-                State::Glyph(glyph)
-            }
-            (State::Glyph(mut glyph), Event::Empty(e)) if e.name() == b"guideline" => {
-                let guideline =
-                    parse_guideline(&reader, e.attributes(), &mut identifier_set, &glyph.format)?;
-                glyph.guidelines.push(guideline);
-                State::Glyph(glyph)
-            }
-            (State::Glyph(mut glyph), Event::Empty(e)) if e.name() == b"advance" => {
+            (State::Glyph, Event::Empty(e)) if e.name() == b"unicode" => {
+                let codepoint = parse_unicode(e.attributes(), limits)?;
+                if !codepoints.contains(&codepoint) {
+                    check_limit("codepoints", codepoints.len() + 1, limits.max_codepoints)?;
+                    codepoints.push(codepoint);
+                    visitor.unicode(codepoint)?;
+                } else if options.strictness == Strictness::Lenient {
+                    warnings.push(Warning::DuplicateCodepoint(codepoint));
+                } else {
+                    return Err(Error::Parse(ErrorKind::DuplicateCodepoint(codepoint)));
+                }
+                State::Glyph
+            }
+            (State::Glyph, Event::Empty(e))
+                if options.skip_anchors_and_guidelines
+                    && (e.name() == b"anchor" || e.name() == b"guideline") =>
+            {
+                State::Glyph
+            }
+            (State::Glyph, Event::Empty(e)) if e.name() == b"anchor" => {
+                counts.anchors += 1;
+                check_limit("anchors", counts.anchors, limits.max_anchors)?;
+                let attributes = input_attributes(xml, &reader, &e, true);
+                let anchor = parse_anchor(attributes, &mut identifier_set, &format, limits)?;
+                visitor.anchor(anchor)?;
+                State::Glyph
+            }
+            (State::Glyph, Event::Empty(e)) if e.name() == b"guideline" => {
+                counts.guidelines += 1;
+                check_limit("guidelines", counts.guidelines, limits.max_guidelines)?;
+                let attributes = input_attributes(xml, &reader, &e, true);
+                let guideline = parse_guideline(attributes, &mut identifier_set, &format, limits)?;
+                visitor.guideline(guideline)?;
+                State::Glyph
+            }
+            (State::Glyph, Event::Empty(e)) if e.name() == b"advance" => {
                 if seen_advance {
                     return Err(Error::Parse(ErrorKind::DuplicateElement));
                 }
                 seen_advance = true;
-                let (height, width) = parse_advance(&reader, e.attributes())?;
-                glyph.height = height;
-                glyph.width = width;
-                State::Glyph(glyph)
+                let (height, width) = parse_advance(e.attributes(), limits)?;
+                visitor.advance(width, height)?;
+                State::Glyph
             }
-            (State::Glyph(mut glyph), Event::Start(e)) if e.name() == b"note" => {
-                if glyph.note.is_some() {
+            (State::Glyph, Event::Start(e)) if e.name() == b"note" => {
+                if seen_note {
+                    return Err(Error::Parse(ErrorKind::DuplicateElement));
+                }
+                seen_note = true;
+                if options.skip_note {
+                    reader.read_to_end(b"note", &mut buf).map_err(Error::Xml)?;
+                } else {
+                    buf.clear();
+                    let note = parse_note(&mut reader, &mut buf, xml, limits)?;
+                    visitor.note(note)?;
+                }
+                State::Glyph
+            }
+            (State::Glyph, Event::Empty(e)) if e.name() == b"note" => {
+                if seen_note {
+                    return Err(Error::Parse(ErrorKind::DuplicateElement));
+                }
+                seen_note = true;
+                if !options.skip_note {
+                    visitor.note(Cow::Borrowed(""))?;
+                }
+                State::Glyph
+            }
+            (State::Glyph, Event::Start(e)) if e.name() == b"lib" => {
+                if seen_lib {
                     return Err(Error::Parse(ErrorKind::DuplicateElement));
                 }
-                let note = parse_note(&mut reader, &mut buf)?;
-                glyph.note = Some(note);
-                State::Glyph(glyph)
+                seen_lib = true;
+                if options.skip_lib {
+                    reader.read_to_end(b"lib", &mut buf).map_err(Error::Xml)?;
+                } else {
+                    buf.clear();
+                    let lib = parse_lib(&mut reader, &mut buf, xml, limits)?;
+                    visitor.lib(lib)?;
+                }
+                State::Glyph
             }
-            (State::Glyph(mut glyph), Event::Start(e)) if e.name() == b"lib" => {
+            (State::Glyph, Event::Empty(e)) if e.name() == b"lib" => {
                 if seen_lib {
                     return Err(Error::Parse(ErrorKind::DuplicateElement));
                 }
                 seen_lib = true;
-                let lib = parse_lib(&mut reader, &mut buf, xml)?;
-                glyph.lib = lib;
-                State::Glyph(glyph)
+                State::Glyph
             }
-            (State::Glyph(mut glyph), Event::Empty(e)) if e.name() == b"image" => {
-                if glyph.image.is_some() {
+            (State::Glyph, Event::Empty(e)) if e.name() == b"image" => {
+                if seen_image {
                     return Err(Error::Parse(ErrorKind::DuplicateElement));
                 }
-                let image = parse_image(&reader, e.attributes())?;
-                glyph.image = Some(image);
-                State::Glyph(glyph)
+                seen_image = true;
+                if !options.skip_image {
+                    let attributes = input_attributes(xml, &reader, &e, true);
+                    let image = parse_image(attributes, limits)?;
+                    visitor.image(image)?;
+                }
+                State::Glyph
+            }
+            (State::Glyph, Event::Start(e)) if e.name() == b"outline" => {
+                begin_outline(&e, &mut seen_outline)?;
+                if options.skip_outline {
+                    reader
+                        .read_to_end(b"outline", &mut buf)
+                        .map_err(Error::Xml)?;
+                    State::Glyph
+                } else {
+                    State::Outline
+                }
+            }
+            (State::Glyph, Event::Empty(e)) if e.name() == b"outline" => {
+                begin_outline(&e, &mut seen_outline)?;
+                State::Glyph
             }
 
-            // Finish up and expect the end of the file.
-            (State::Glyph(glyph), Event::End(e)) if e.name() == b"glyph" => {
-                // TODO: move object libs
-                State::Done(glyph)
+            // Handle the content of <outline>.
+            (State::Outline, Event::Start(e)) if e.name() == b"contour" => {
+                counts.contours += 1;
+                check_limit("contours", counts.contours, limits.max_contours)?;
+                let identifier =
+                    parse_contour(e.attributes(), &mut identifier_set, &format, limits)?;
+                visitor.begin_contour(identifier)?;
+                State::Contour
+            }
+            (State::Outline, Event::Empty(e)) if e.name() == b"contour" => {
+                counts.contours += 1;
+                check_limit("contours", counts.contours, limits.max_contours)?;
+                let identifier =
+                    parse_contour(e.attributes(), &mut identifier_set, &format, limits)?;
+                visitor.begin_contour(identifier)?;
+                visitor.end_contour()?;
+                State::Outline
+            }
+            (State::Outline, Event::Empty(e)) if e.name() == b"component" => {
+                counts.components += 1;
+                check_limit("components", counts.components, limits.max_components)?;
+                let attributes = input_attributes(xml, &reader, &e, true);
+                let component = parse_component(attributes, &mut identifier_set, &format, limits)?;
+                visitor.component(component)?;
+                State::Outline
+            }
+            (State::Outline, Event::End(e)) if e.name() == b"outline" => State::Glyph,
+
+            // Handle the content of <contour>.
+            (State::Contour, Event::Empty(e)) if e.name() == b"point" => {
+                counts.points += 1;
+                check_limit("points", counts.points, limits.max_points)?;
+                let attributes = input_attributes(xml, &reader, &e, true);
+                let point = parse_point(attributes, &mut identifier_set, &format, limits)?;
+                visitor.point(point)?;
+                State::Contour
+            }
+            (State::Contour, Event::End(e)) if e.name() == b"contour" => {
+                visitor.end_contour()?;
+                State::Outline
             }
-            (State::Done(glyph), Event::Eof) => return Ok(glyph),
-            (State::Done(_), _) => return Err(Error::Parse(ErrorKind::TrailingData)),
+
+            // Finish up and expect the end of the file.
+            (State::Glyph, Event::End(e)) if e.name() == b"glyph" => State::Done,
+            (State::Done, Event::Eof) => return Ok(warnings),
+            (State::Done, _) => return Err(Error::Parse(ErrorKind::TrailingData)),
 
             // Anything else is an error.
             (_, Event::Eof) => return Err(Error::Parse(ErrorKind::UnexpectedEof)),
-            (state, _) => state, // TODO: error out
+            (_, Event::Start(e) | Event::Empty(e)) => return Err(unexpected_element(e.name())),
+            (_, Event::End(e)) => return Err(unexpected_element(e.name())),
+            (_, Event::Text(_) | Event::CData(_)) => {
+                return Err(Error::Parse(ErrorKind::UnexpectedText))
+            }
+            (_, Event::Decl(_)) => return Err(Error::Parse(ErrorKind::MisplacedDeclaration)),
         };
         buf.clear();
     }
 }
 
-fn parse_glyph(reader: &Reader<&[u8]>, attributes: Attributes) -> Result<Glyph, Error> {
-    let mut name = String::new();
+/// Check an `<outline>` element, which may only appear once and has no attributes.
+fn begin_outline(element: &BytesStart, seen_outline: &mut bool) -> Result<(), Error> {
+    if *seen_outline {
+        return Err(Error::Parse(ErrorKind::DuplicateElement));
+    }
+    *seen_outline = true;
+    if element.attributes().next().is_some() {
+        return Err(Error::Parse(ErrorKind::UnexpectedAttribute));
+    }
+    Ok(())
+}
+
+fn unexpected_element(name: &[u8]) -> Error {
+    Error::Parse(ErrorKind::UnexpectedElement(
+        String::from_utf8_lossy(name).into_owned(),
+    ))
+}
+
+/// Read the attributes of the element just read straight from the input buffer.
+///
+/// The reader copies each element into its scratch buffer; going back to the input
+/// instead lets attribute values borrow from it.
+fn input_attributes<'a>(
+    xml: &'a [u8],
+    reader: &Reader<&[u8]>,
+    e: &BytesStart,
+    empty: bool,
+) -> Attributes<'a> {
+    // Skip the closing `>`, or `/>` of an empty element.
+    let end = if empty {
+        reader.buffer_position() - 2
+    } else {
+        reader.buffer_position() - 1
+    };
+    let start = end - e.len();
+    debug_assert_eq!(&xml[start..end], &**e);
+    Attributes::new(&xml[start..end], e.name().len())
+}
+
+/// Running totals of repeatable elements, to check against the [`Limits`].
+#[derive(Debug, Default)]
+struct ElementCounts {
+    anchors: usize,
+    guidelines: usize,
+    contours: usize,
+    points: usize,
+    components: usize,
+}
+
+/// Check that the glif file is UTF-8, as the specification demands, and strip a
+/// byte order mark if present.
+///
+/// Validating the whole buffer up front means that decoding individual attributes
+/// and text later on can't fail.
+fn check_encoding(xml: &[u8]) -> Result<&[u8], Error> {
+    if xml.starts_with(&[0xFE, 0xFF]) || xml.starts_with(&[0xFF, 0xFE]) {
+        return Err(Error::Parse(ErrorKind::UnsupportedEncoding(
+            "UTF-16".into(),
+        )));
+    }
+    let xml = xml.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(xml);
+    std::str::from_utf8(xml).map_err(|e| Error::Parse(ErrorKind::InvalidUtf8(e.valid_up_to())))?;
+    Ok(xml)
+}
+
+/// Check that the XML declaration, if any, declares XML 1.0 and UTF-8.
+fn check_declaration(decl: &BytesDecl) -> Result<(), Error> {
+    let version = decl.version().map_err(Error::Xml)?;
+    if version.as_ref() != b"1.0" {
+        return Err(Error::Parse(ErrorKind::UnsupportedXmlVersion(
+            String::from_utf8_lossy(&version).into_owned(),
+        )));
+    }
+    if let Some(encoding) = decl.encoding() {
+        let encoding = encoding.map_err(Error::Xml)?;
+        if !encoding.eq_ignore_ascii_case(b"UTF-8") {
+            return Err(Error::Parse(ErrorKind::UnsupportedEncoding(
+                String::from_utf8_lossy(&encoding).into_owned(),
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The parts of a glyph needed to index a glyph set: its name, format and codepoints.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlifHeader {
+    pub name: GlyphName,
+    pub format: GlifVersion,
+    pub format_minor: u32,
+    pub codepoints: Vec<char>,
+}
+
+/// Read only the name, format and codepoints of a glif file.
+///
+/// This is much faster than [`parse_glif`] because everything else is skipped without
+/// being parsed or validated: child elements like `<outline>` and `<lib>` are
+/// fast-forwarded over as a whole. The specification doesn't fix the order of
+/// elements, so `<unicode>` elements after them are still found. Reading stops at
+/// the closing `</glyph>` tag.
+pub fn read_glif_header(xml: &[u8]) -> Result<GlifHeader, Error> {
+    let limits = Limits::default();
+    check_limit("input size", xml.len(), limits.max_input_size)?;
+    let xml = check_encoding(xml)?;
+    let mut reader = Reader::from_reader(xml);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    let mut header: Option<GlifHeader> = None;
+
+    loop {
+        match (
+            &mut header,
+            reader.read_event(&mut buf).map_err(Error::Xml)?,
+        ) {
+            (_, Event::DocType(_)) => return Err(Error::Parse(ErrorKind::DocTypeNotAllowed)),
+            (_, Event::PI(_)) => {
+                return Err(Error::Parse(ErrorKind::ProcessingInstructionNotAllowed))
+            }
+            (None, Event::Decl(e)) => check_declaration(&e)?,
+            (None, Event::Start(e)) if e.name() == b"glyph" => {
+                let (name, format, format_minor) = parse_glyph(e.attributes(), &limits)?;
+                header = Some(GlifHeader {
+                    name: (&*name).into(),
+                    format,
+                    format_minor,
+                    codepoints: Vec::new(),
+                });
+            }
+            (None, Event::Empty(_) | Event::Start(_)) => {
+                return Err(Error::Parse(ErrorKind::WrongFirstElement))
+            }
+            (Some(header), Event::Empty(e)) if e.name() == b"unicode" => {
+                let codepoint = parse_unicode(e.attributes(), &limits)?;
+                if header.codepoints.contains(&codepoint) {
+                    return Err(Error::Parse(ErrorKind::DuplicateCodepoint(codepoint)));
+                }
+                check_limit(
+                    "codepoints",
+                    header.codepoints.len() + 1,
+                    limits.max_codepoints,
+                )?;
+                header.codepoints.push(codepoint);
+            }
+            (Some(_), Event::Start(e)) => {
+                let name = e.name().to_vec();
+                reader.read_to_end(name, &mut buf).map_err(Error::Xml)?;
+            }
+            (Some(_), Event::End(e)) if e.name() == b"glyph" => break,
+            (_, Event::Eof) => return Err(Error::Parse(ErrorKind::UnexpectedEof)),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(header.unwrap())
+}
+
+fn parse_glyph<'a>(
+    attributes: Attributes<'a>,
+    limits: &Limits,
+) -> Result<(Cow<'a, str>, GlifVersion, u32), Error> {
+    let mut name: Option<Cow<'a, str>> = None;
     let mut format: Option<GlifVersion> = None;
     let mut format_minor: u32 = 0;
 
     for attr in attributes {
         let attr = attr.map_err(Error::Xml)?;
-        let value = attr.unescaped_value().map_err(Error::Xml)?;
-        let value = reader.decode(&value).map_err(Error::Xml)?;
+        let value = attr.value;
         match attr.key {
-            b"name" => name.push_str(value),
+            b"name" => name = Some(attribute_str(value, limits)?),
             b"format" => {
-                format = match value {
+                format = match &*attribute_str(value, limits)? {
                     "1" => Some(GlifVersion::V1),
                     "2" => Some(GlifVersion::V2),
                     _ => return Err(Error::Parse(ErrorKind::UnsupportedGlifVersion)),
                 }
             }
             b"formatMinor" => {
+                let value = attribute_str(value, limits)?;
                 format_minor = value
                     .parse()
-                    .map_err(|e| Error::Parse(ErrorKind::InvalidInteger(value.into(), e)))?;
+                    .map_err(|e| Error::Parse(ErrorKind::InvalidInteger(value.to_string(), e)))?;
             }
             _ => return Err(Error::Parse(ErrorKind::UnexpectedAttribute)),
         }
     }
 
-    if !name.is_empty() && format.is_some() {
-        let mut glyph = Glyph::new_named(name);
-        glyph.format = format.take().unwrap();
+    match (name, format) {
         // The formatMinor attribute is a UFO v3 thing, but it may not be
         // worth the hassle to be really pedantic about it.
-        glyph.format_minor = format_minor;
-
-        Ok(glyph)
-    } else {
-        Err(Error::Parse(ErrorKind::InvalidGlyph))
+        (Some(name), Some(format)) if !name.is_empty() => Ok((name, format, format_minor)),
+        _ => Err(Error::Parse(ErrorKind::InvalidGlyph)),
     }
 }
 
-fn parse_advance(reader: &Reader<&[u8]>, attributes: Attributes) -> Result<(f64, f64), Error> {
+fn parse_advance(attributes: Attributes, limits: &Limits) -> Result<(f64, f64), Error> {
     let mut width: f64 = 0.0;
     let mut height: f64 = 0.0;
 
     for attr in attributes {
         let attr = attr.map_err(Error::Xml)?;
-        let value = attr.unescaped_value().map_err(Error::Xml)?;
-        let value = reader.decode(&value).map_err(Error::Xml)?;
+        let value = attr.value;
         match attr.key {
-            b"height" => height = parse_number(value)?,
-            b"width" => width = parse_number(value)?,
+            b"height" => height = number_attribute(&value, limits)?,
+            b"width" => width = number_attribute(&value, limits)?,
             _ => return Err(Error::Parse(ErrorKind::UnexpectedAttribute)),
         }
     }
@@ -231,15 +766,14 @@ fn parse_advance(reader: &Reader<&[u8]>, attributes: Attributes) -> Result<(f64,
     Ok((height, width))
 }
 
-fn parse_unicode(reader: &Reader<&[u8]>, attributes: Attributes) -> Result<char, Error> {
+fn parse_unicode(attributes: Attributes, limits: &Limits) -> Result<char, Error> {
     let mut codepoint = None;
 
     for attr in attributes {
         let attr = attr.map_err(Error::Xml)?;
-        let value = attr.unescaped_value().map_err(Error::Xml)?;
-        let value = reader.decode(&value).map_err(Error::Xml)?;
+        let value = attr.value;
         match attr.key {
-            b"hex" => codepoint = Some(parse_codepoint(value)?),
+            b"hex" => codepoint = Some(codepoint_attribute(&value, limits)?),
             _ => return Err(Error::Parse(ErrorKind::UnexpectedAttribute)),
         }
     }
@@ -250,71 +784,83 @@ fn parse_unicode(reader: &Reader<&[u8]>, attributes: Attributes) -> Result<char,
     }
 }
 
-fn parse_anchor(
-    reader: &Reader<&[u8]>,
-    attributes: Attributes,
+fn parse_anchor<'a>(
+    attributes: Attributes<'a>,
     identifier_set: &mut HashSet<Identifier>,
     glif_format: &GlifVersion,
-) -> Result<Anchor, Error> {
+    limits: &Limits,
+) -> Result<AnchorRef<'a>, Error> {
     let mut x: Option<f64> = None;
     let mut y: Option<f64> = None;
-    let mut name: Option<String> = None;
+    let mut name: Option<Cow<'a, str>> = None;
     let mut color: Option<Color> = None;
     let mut identifier: Option<Identifier> = None;
 
     for attr in attributes {
         let attr = attr.map_err(Error::Xml)?;
-        let value = attr.unescaped_value().map_err(Error::Xml)?;
-        let value = reader.decode(&value).map_err(Error::Xml)?;
+        let value = attr.value;
         match attr.key {
-            b"x" => x = Some(parse_number(value)?),
-            b"y" => y = Some(parse_number(value)?),
-            b"name" => name = Some(value.to_string()),
-            b"color" => color = Some(parse_color(value)?),
+            b"x" => x = Some(number_attribute(&value, limits)?),
+            b"y" => y = Some(number_attribute(&value, limits)?),
+            b"name" => name = Some(attribute_str(value, limits)?),
+            b"color" => color = Some(parse_color(&attribute_str(value, limits)?)?),
             b"identifier" => {
-                identifier = Some(parse_identifier(value, identifier_set, glif_format)?);
+                identifier = Some(parse_identifier(
+                    &attribute_str(value, limits)?,
+                    identifier_set,
+                    glif_format,
+                )?);
             }
             _ => return Err(Error::Parse(ErrorKind::UnexpectedAttribute)),
         }
     }
 
     match (x, y) {
-        (Some(x), Some(y)) => Ok(Anchor::new(x, y, name, color, identifier, None)),
+        (Some(x), Some(y)) => Ok(AnchorRef {
+            x,
+            y,
+            name,
+            color,
+            identifier,
+        }),
         _ => Err(Error::Parse(ErrorKind::InvalidAnchor)),
     }
 }
 
-fn parse_guideline(
-    reader: &Reader<&[u8]>,
-    attributes: Attributes,
+fn parse_guideline<'a>(
+    attributes: Attributes<'a>,
     identifier_set: &mut HashSet<Identifier>,
     glif_format: &GlifVersion,
-) -> Result<Guideline, Error> {
+    limits: &Limits,
+) -> Result<GuidelineRef<'a>, Error> {
     let mut x: Option<f64> = None;
     let mut y: Option<f64> = None;
     let mut angle: Option<f64> = None;
-    let mut name: Option<String> = None;
+    let mut name: Option<Cow<'a, str>> = None;
     let mut color: Option<Color> = None;
     let mut identifier: Option<Identifier> = None;
 
     for attr in attributes {
         let attr = attr.map_err(Error::Xml)?;
-        let value = attr.unescaped_value().map_err(Error::Xml)?;
-        let value = reader.decode(&value).map_err(Error::Xml)?;
+        let value = attr.value;
         match attr.key {
-            b"x" => x = Some(parse_number(value)?),
-            b"y" => y = Some(parse_number(value)?),
+            b"x" => x = Some(number_attribute(&value, limits)?),
+            b"y" => y = Some(number_attribute(&value, limits)?),
             b"angle" => {
-                let angle_value = parse_number(value)?;
+                let angle_value = number_attribute(&value, limits)?;
                 if !(0.0..=360.0).contains(&angle_value) {
                     return Err(Error::Parse(ErrorKind::InvalidAngle));
                 }
                 angle = Some(angle_value);
             }
-            b"name" => name = Some(value.to_string()),
-            b"color" => color = Some(parse_color(value)?),
+            b"name" => name = Some(attribute_str(value, limits)?),
+            b"color" => color = Some(parse_color(&attribute_str(value, limits)?)?),
             b"identifier" => {
-                identifier = Some(parse_identifier(value, identifier_set, glif_format)?);
+                identifier = Some(parse_identifier(
+                    &attribute_str(value, limits)?,
+                    identifier_set,
+                    glif_format,
+                )?);
             }
             _ => return Err(Error::Parse(ErrorKind::UnexpectedAttribute)),
         }
@@ -327,54 +873,288 @@ fn parse_guideline(
         _ => return Err(Error::Parse(ErrorKind::InvalidGuideline)),
     };
 
-    Ok(Guideline::new(line, name, color, identifier, None))
+    Ok(GuidelineRef {
+        line,
+        name,
+        color,
+        identifier,
+    })
 }
 
-fn parse_note(reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>) -> Result<String, Error> {
-    reader.read_text(b"note", buf).map_err(Error::Xml)
+fn parse_contour(
+    attributes: Attributes,
+    identifier_set: &mut HashSet<Identifier>,
+    glif_format: &GlifVersion,
+    limits: &Limits,
+) -> Result<Option<Identifier>, Error> {
+    let mut identifier: Option<Identifier> = None;
+
+    for attr in attributes {
+        let attr = attr.map_err(Error::Xml)?;
+        let value = attr.value;
+        match attr.key {
+            b"identifier" => {
+                identifier = Some(parse_identifier(
+                    &attribute_str(value, limits)?,
+                    identifier_set,
+                    glif_format,
+                )?);
+            }
+            _ => return Err(Error::Parse(ErrorKind::UnexpectedAttribute)),
+        }
+    }
+
+    Ok(identifier)
+}
+
+fn parse_point<'a>(
+    attributes: Attributes<'a>,
+    identifier_set: &mut HashSet<Identifier>,
+    glif_format: &GlifVersion,
+    limits: &Limits,
+) -> Result<PointRef<'a>, Error> {
+    let mut x: Option<f64> = None;
+    let mut y: Option<f64> = None;
+    let mut typ = PointType::OffCurve;
+    let mut smooth = false;
+    let mut name: Option<Cow<'a, str>> = None;
+    let mut identifier: Option<Identifier> = None;
+
+    for attr in attributes {
+        let attr = attr.map_err(Error::Xml)?;
+        let value = attr.value;
+        match attr.key {
+            b"x" => x = Some(number_attribute(&value, limits)?),
+            b"y" => y = Some(number_attribute(&value, limits)?),
+            b"type" => {
+                typ = match &*attribute_str(value, limits)? {
+                    "move" => PointType::Move,
+                    "line" => PointType::Line,
+                    "offcurve" => PointType::OffCurve,
+                    "curve" => PointType::Curve,
+                    "qcurve" => PointType::QCurve,
+                    _ => return Err(Error::Parse(ErrorKind::InvalidPoint)),
+                }
+            }
+            b"smooth" => {
+                smooth = match &*attribute_str(value, limits)? {
+                    "yes" => true,
+                    "no" => false,
+                    _ => return Err(Error::Parse(ErrorKind::InvalidPoint)),
+                }
+            }
+            b"name" => name = Some(attribute_str(value, limits)?),
+            b"identifier" => {
+                identifier = Some(parse_identifier(
+                    &attribute_str(value, limits)?,
+                    identifier_set,
+                    glif_format,
+                )?);
+            }
+            _ => return Err(Error::Parse(ErrorKind::UnexpectedAttribute)),
+        }
+    }
+
+    match (x, y) {
+        (Some(x), Some(y)) => Ok(PointRef {
+            x,
+            y,
+            typ,
+            smooth,
+            name,
+            identifier,
+        }),
+        _ => Err(Error::Parse(ErrorKind::InvalidPoint)),
+    }
+}
+
+fn parse_component<'a>(
+    attributes: Attributes<'a>,
+    identifier_set: &mut HashSet<Identifier>,
+    glif_format: &GlifVersion,
+    limits: &Limits,
+) -> Result<ComponentRef<'a>, Error> {
+    let mut base: Option<Cow<'a, str>> = None;
+    let mut transform = AffineTransform::default();
+    let mut identifier: Option<Identifier> = None;
+
+    for attr in attributes {
+        let attr = attr.map_err(Error::Xml)?;
+        let value = attr.value;
+        match attr.key {
+            b"base" => base = Some(attribute_str(value, limits)?),
+            b"xScale" => transform.x_scale = number_attribute(&value, limits)?,
+            b"xyScale" => transform.xy_scale = number_attribute(&value, limits)?,
+            b"yxScale" => transform.yx_scale = number_attribute(&value, limits)?,
+            b"yScale" => transform.y_scale = number_attribute(&value, limits)?,
+            b"xOffset" => transform.x_offset = number_attribute(&value, limits)?,
+            b"yOffset" => transform.y_offset = number_attribute(&value, limits)?,
+            b"identifier" => {
+                identifier = Some(parse_identifier(
+                    &attribute_str(value, limits)?,
+                    identifier_set,
+                    glif_format,
+                )?);
+            }
+            _ => return Err(Error::Parse(ErrorKind::UnexpectedAttribute)),
+        }
+    }
+
+    match base {
+        Some(base) if !base.is_empty() => Ok(ComponentRef {
+            base,
+            transform,
+            identifier,
+        }),
+        _ => Err(Error::Parse(ErrorKind::InvalidComponent)),
+    }
+}
+
+/// Read the content of a <note> element verbatim.
+///
+/// The reader trims whitespace around text globally to skip indentation between
+/// elements, but a note's leading and trailing whitespace and line breaks are content,
+/// so turn trimming off until the closing tag.
+fn parse_note<'a>(
+    reader: &mut Reader<&[u8]>,
+    buf: &mut Vec<u8>,
+    xml: &'a [u8],
+    limits: &Limits,
+) -> Result<Cow<'a, str>, Error> {
+    reader.trim_text(false);
+    let note = read_text(reader, buf, xml, b"note", limits.max_string_length, || {
+        Error::Parse(ErrorKind::InvalidNote)
+    })?;
+    reader.trim_text(true);
+    Ok(note)
+}
+
+/// Read text up to the closing `end` tag, borrowing it from the input if it is a
+/// single run of text without escapes. Elements in between are reported with the
+/// error `invalid` returns.
+///
+/// Whitespace is significant; trimming must be turned off by the caller.
+pub(crate) fn read_text<'a>(
+    reader: &mut Reader<&[u8]>,
+    buf: &mut Vec<u8>,
+    xml: &'a [u8],
+    end: &[u8],
+    max_length: usize,
+    invalid: fn() -> Error,
+) -> Result<Cow<'a, str>, Error> {
+    let mut text: Option<Cow<'a, str>> = None;
+    loop {
+        let start = reader.buffer_position();
+        let piece: Cow<'a, str> = match reader.read_event(buf).map_err(Error::Xml)? {
+            Event::Text(e) => {
+                check_entities(e.escaped())?;
+                let raw = &xml[start..start + e.len()];
+                if raw == &*e && !raw.contains(&b'&') {
+                    std::str::from_utf8(raw)
+                        .map(Cow::Borrowed)
+                        .map_err(|e| Error::Parse(ErrorKind::InvalidUtf8(e.valid_up_to())))?
+                } else {
+                    Cow::Owned(e.unescape_and_decode(reader).map_err(Error::Xml)?)
+                }
+            }
+            // quick-xml hands out CDATA content escaped, so unescape it like text.
+            Event::CData(e) => Cow::Owned(e.unescape_and_decode(reader).map_err(Error::Xml)?),
+            Event::Comment(_) => {
+                buf.clear();
+                continue;
+            }
+            Event::PI(_) => return Err(Error::Parse(ErrorKind::ProcessingInstructionNotAllowed)),
+            Event::End(e) if e.name() == end => break,
+            Event::Eof => return Err(Error::Parse(ErrorKind::UnexpectedEof)),
+            _ => return Err(invalid()),
+        };
+        text = Some(match text {
+            None => piece,
+            Some(text) => Cow::Owned(text.into_owned() + &piece),
+        });
+        check_limit(
+            "string length",
+            text.as_ref().map_or(0, |text| text.len()),
+            max_length,
+        )?;
+        buf.clear();
+    }
+    buf.clear();
+    Ok(text.unwrap_or_default())
 }
 
-fn parse_lib(reader: &mut Reader<&[u8]>, buf: &mut Vec<u8>, xml: &[u8]) -> Result<Plist, Error> {
+/// Check the content of a <lib> element and return it for the visitor to parse.
+fn parse_lib<'a>(
+    reader: &mut Reader<&[u8]>,
+    buf: &mut Vec<u8>,
+    xml: &'a [u8],
+    limits: &Limits,
+) -> Result<RawLib<'a>, Error> {
     // The plist crate currently uses a different XML parsing library internally, so
     // we can't pass over control to it directly. Instead, pass it the precise slice
     // of the raw buffer to parse.
+    //
+    // Skip to the closing </lib> ourselves rather than with `read_to_end`, so that
+    // nothing the plist parser might act on (DOCTYPEs, processing instructions or
+    // unknown entities) slips through.
     let start = reader.buffer_position();
-    reader.read_to_end(b"lib", buf).map_err(Error::Xml)?;
-    let end = reader.buffer_position();
-    let plist_slice = &xml[start..end];
-
-    let dict = plist::Value::from_reader_xml(plist_slice)
-        .map_err(|source| Error::Parse(ErrorKind::ParsePlist(source.into())))?
-        .into_dictionary()
-        .ok_or(Error::Parse(ErrorKind::LibMustBeDictionary))?;
-
-    Ok(dict)
+    let mut depth: usize = 0;
+    let end = loop {
+        let position = reader.buffer_position();
+        match reader.read_event(buf).map_err(Error::Xml)? {
+            Event::Start(_) => {
+                depth += 1;
+                check_limit("lib nesting depth", depth, limits.max_lib_depth)?;
+            }
+            Event::Empty(_) => check_limit("lib nesting depth", depth + 1, limits.max_lib_depth)?,
+            Event::End(_) if depth == 0 => break position,
+            Event::End(_) => depth -= 1,
+            Event::Text(e) => {
+                check_limit("string length", e.len(), limits.max_string_length)?;
+                check_entities(e.escaped())?;
+            }
+            Event::DocType(_) => return Err(Error::Parse(ErrorKind::DocTypeNotAllowed)),
+            Event::PI(_) => return Err(Error::Parse(ErrorKind::ProcessingInstructionNotAllowed)),
+            Event::Decl(_) => return Err(Error::Parse(ErrorKind::MisplacedDeclaration)),
+            Event::Eof => return Err(Error::Parse(ErrorKind::UnexpectedEof)),
+            _ => {}
+        }
+        buf.clear();
+    };
+    Ok(RawLib::new(&xml[start..end], limits))
 }
 
-fn parse_image(reader: &Reader<&[u8]>, attributes: Attributes) -> Result<Image, Error> {
-    let mut filename: Option<PathBuf> = None;
+fn parse_image<'a>(attributes: Attributes<'a>, limits: &Limits) -> Result<ImageRef<'a>, Error> {
+    let mut file_name: Option<Cow<'a, str>> = None;
     let mut color: Option<Color> = None;
     let mut transform = AffineTransform::default();
 
     for attr in attributes {
         let attr = attr.map_err(Error::Xml)?;
-        let value = attr.unescaped_value().map_err(Error::Xml)?;
-        let value = reader.decode(&value).map_err(Error::Xml)?;
+        let value = attr.value;
         match attr.key {
-            b"xScale" => transform.x_scale = parse_number(value)?,
-            b"xyScale" => transform.xy_scale = parse_number(value)?,
-            b"yxScale" => transform.yx_scale = parse_number(value)?,
-            b"yScale" => transform.y_scale = parse_number(value)?,
-            b"xOffset" => transform.x_offset = parse_number(value)?,
-            b"yOffset" => transform.y_offset = parse_number(value)?,
-            b"color" => color = Some(parse_color(value)?),
-            b"fileName" => filename = Some(PathBuf::from(value.to_string())),
+            b"xScale" => transform.x_scale = number_attribute(&value, limits)?,
+            b"xyScale" => transform.xy_scale = number_attribute(&value, limits)?,
+            b"yxScale" => transform.yx_scale = number_attribute(&value, limits)?,
+            b"yScale" => transform.y_scale = number_attribute(&value, limits)?,
+            b"xOffset" => transform.x_offset = number_attribute(&value, limits)?,
+            b"yOffset" => transform.y_offset = number_attribute(&value, limits)?,
+            b"color" => color = Some(parse_color(&attribute_str(value, limits)?)?),
+            b"fileName" => {
+                let value = attribute_str(value, limits)?;
+                if !is_bare_file_name(&value) {
+                    return Err(Error::Parse(ErrorKind::InvalidImageFileName(
+                        value.into_owned(),
+                    )));
+                }
+                file_name = Some(value)
+            }
             _ => return Err(Error::Parse(ErrorKind::UnexpectedAttribute)),
         }
     }
 
-    match filename {
-        Some(file_name) => Ok(Image {
+    match file_name {
+        Some(file_name) => Ok(ImageRef {
             file_name,
             color,
             transform,
@@ -383,16 +1163,203 @@ fn parse_image(reader: &Reader<&[u8]>, attributes: Attributes) -> Result<Image,
     }
 }
 
+/// Decode an attribute value, borrowing it unless it needs unescaping.
+fn attribute_str<'a>(value: Cow<'a, [u8]>, limits: &Limits) -> Result<Cow<'a, str>, Error> {
+    check_attribute(&value, limits)?;
+    match value {
+        Cow::Borrowed(bytes) if !bytes.contains(&b'&') => std::str::from_utf8(bytes)
+            .map(Cow::Borrowed)
+            .map_err(|e| Error::Parse(ErrorKind::InvalidUtf8(e.valid_up_to()))),
+        value => {
+            let unescaped = quick_xml::escape::unescape(&value)
+                .map_err(|e| Error::Xml(quick_xml::Error::EscapeError(e)))?;
+            String::from_utf8(unescaped.into_owned())
+                .map(Cow::Owned)
+                .map_err(|e| Error::Parse(ErrorKind::InvalidUtf8(e.utf8_error().valid_up_to())))
+        }
+    }
+}
+
+/// Parse a number attribute straight from its raw bytes.
+///
+/// Numbers have no business containing escapes, so unescaping and decoding are only
+/// done for the odd value that has them anyway.
+fn number_attribute(value: &[u8], limits: &Limits) -> Result<f64, Error> {
+    if value.contains(&b'&') {
+        return parse_number(&attribute_str(Cow::Borrowed(value), limits)?);
+    }
+    check_limit("string length", value.len(), limits.max_string_length)?;
+    parse_number_bytes(value)
+}
+
+/// Parse a codepoint attribute straight from its raw bytes, like [`number_attribute`].
+fn codepoint_attribute(value: &[u8], limits: &Limits) -> Result<char, Error> {
+    if value.contains(&b'&') {
+        return parse_codepoint(&attribute_str(Cow::Borrowed(value), limits)?);
+    }
+    check_limit("string length", value.len(), limits.max_string_length)?;
+    parse_codepoint_bytes(value)
+}
+
+fn check_limit(what: &'static str, value: usize, limit: usize) -> Result<(), Error> {
+    if value > limit {
+        return Err(Error::Parse(ErrorKind::LimitExceeded { what, limit }));
+    }
+    Ok(())
+}
+
+/// Check a raw attribute value before unescaping it.
+fn check_attribute(value: &[u8], limits: &Limits) -> Result<(), Error> {
+    check_limit("string length", value.len(), limits.max_string_length)?;
+    check_entities(value)
+}
+
+/// Check that escaped text only references the predefined XML entities or characters.
+fn check_entities(escaped: &[u8]) -> Result<(), Error> {
+    let mut rest = escaped;
+    while let Some(start) = rest.iter().position(|&b| b == b'&') {
+        rest = &rest[start + 1..];
+        let end = match rest.iter().position(|&b| b == b';') {
+            Some(end) => end,
+            // Let the unescaper report the unterminated reference.
+            None => return Ok(()),
+        };
+        match &rest[..end] {
+            b"lt" | b"gt" | b"amp" | b"apos" | b"quot" => {}
+            [b'#', ..] => {}
+            name => {
+                return Err(Error::Parse(ErrorKind::UnknownEntity(
+                    String::from_utf8_lossy(name).into_owned(),
+                )))
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    Ok(())
+}
+
+/// Whether `value` names a file directly inside a directory, i.e. has no path
+/// separators and doesn't refer to the directory itself or its parent.
+fn is_bare_file_name(value: &str) -> bool {
+    !value.is_empty()
+        && value != "."
+        && value != ".."
+        && !value.contains(['/', '\\', '\0'])
+        && !Path::new(value).has_root()
+}
+
+/// Check that the file referenced by an image exists in the `images` directory of
+/// the UFO at `ufo_root` and is a PNG file.
+///
+/// The parser only checks that `fileName` is a bare file name; use this to report
+/// broken background image references at load time.
+pub fn check_image_file(image: &Image, ufo_root: &Path) -> Result<(), Error> {
+    const PNG_SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+    let file_name = image.file_name.to_string_lossy();
+    if !is_bare_file_name(&file_name) {
+        return Err(Error::Parse(ErrorKind::InvalidImageFileName(
+            file_name.into_owned(),
+        )));
+    }
+
+    let path = ufo_root.join("images").join(&image.file_name);
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(Error::Parse(ErrorKind::ImageFileMissing(path)))
+        }
+        Err(e) => return Err(Error::Io(path, e)),
+    };
+    let mut signature = [0; 8];
+    match file.read_exact(&mut signature) {
+        Ok(()) if &signature == PNG_SIGNATURE => Ok(()),
+        Ok(()) => Err(Error::Parse(ErrorKind::ImageNotPng(path))),
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+            Err(Error::Parse(ErrorKind::ImageNotPng(path)))
+        }
+        Err(e) => Err(Error::Io(path, e)),
+    }
+}
+
 fn parse_codepoint(value: &str) -> Result<char, Error> {
-    let i = u32::from_str_radix(value, 16)
-        .map_err(|e| Error::Parse(ErrorKind::InvalidCodepoint(value.into(), e.into())))?;
-    char::try_from(i).map_err(|e| Error::Parse(ErrorKind::InvalidCodepoint(value.into(), e.into())))
+    parse_codepoint_bytes(value.as_bytes())
+}
+
+/// Parse a codepoint given as hexadecimal digits, without sign or prefix.
+fn parse_codepoint_bytes(value: &[u8]) -> Result<char, Error> {
+    let text = || String::from_utf8_lossy(value).into_owned();
+    if value.is_empty() || !value.iter().all(u8::is_ascii_hexdigit) {
+        return Err(Error::Parse(ErrorKind::MalformedHex(text())));
+    }
+    let mut i: u32 = 0;
+    for &digit in value {
+        // Only hex digits are left after the check above.
+        let digit = (digit as char).to_digit(16).unwrap();
+        i = i
+            .checked_mul(16)
+            .and_then(|i| i.checked_add(digit))
+            .filter(|&i| i <= char::MAX as u32)
+            .ok_or_else(|| Error::Parse(ErrorKind::CodepointOutOfRange(text())))?;
+    }
+    char::try_from(i).map_err(|e| Error::Parse(ErrorKind::InvalidCodepoint(text(), e.into())))
 }
 
 fn parse_number(value: &str) -> Result<f64, Error> {
-    value
+    parse_number_bytes(value.as_bytes())
+}
+
+/// Parse a finite number following the grammar `-?(\d+(\.\d*)?|\.\d+)([eE][+-]?\d+)?`.
+///
+/// Rust's float parser is more liberal than that (it accepts `inf`, `NaN` and a leading
+/// `+`), so check the grammar first and reject anything that overflows to infinity.
+fn parse_number_bytes(value: &[u8]) -> Result<f64, Error> {
+    let malformed = || {
+        Error::Parse(ErrorKind::MalformedNumber(
+            String::from_utf8_lossy(value).into(),
+        ))
+    };
+    if !is_number(value) {
+        return Err(malformed());
+    }
+    // The grammar only admits ASCII, so this can't fail.
+    let text = std::str::from_utf8(value).map_err(|_| malformed())?;
+    let number: f64 = text
         .parse()
-        .map_err(|e| Error::Parse(ErrorKind::InvalidNumber(value.into(), e)))
+        .map_err(|e| Error::Parse(ErrorKind::InvalidNumber(text.into(), e)))?;
+    if !number.is_finite() {
+        return Err(Error::Parse(ErrorKind::NumberOutOfRange(text.into())));
+    }
+    Ok(number)
+}
+
+fn is_number(value: &[u8]) -> bool {
+    fn skip_digits(value: &[u8]) -> (&[u8], usize) {
+        let n = value.iter().take_while(|b| b.is_ascii_digit()).count();
+        (&value[n..], n)
+    }
+
+    let value = value.strip_prefix(b"-").unwrap_or(value);
+    let (value, integer_digits) = skip_digits(value);
+    let (value, fraction_digits) = match value.strip_prefix(b".") {
+        Some(value) => skip_digits(value),
+        None => (value, 0),
+    };
+    if integer_digits == 0 && fraction_digits == 0 {
+        return false;
+    }
+    match value {
+        [] => true,
+        [b'e' | b'E', exponent @ ..] => {
+            let exponent = match exponent {
+                [b'+' | b'-', rest @ ..] => rest,
+                rest => rest,
+            };
+            let (rest, exponent_digits) = skip_digits(exponent);
+            rest.is_empty() && exponent_digits > 0
+        }
+        _ => false,
+    }
 }
 
 fn parse_color(value: &str) -> Result<Color, Error> {
@@ -418,6 +1385,7 @@ fn parse_identifier(
 
 #[cfg(test)]
 mod tests {
+    use norad::{Anchor, Component, Contour, ContourPoint, Guideline};
     use pretty_assertions::assert_eq;
 
     use super::*;
@@ -675,6 +1643,578 @@ mod tests {
         assert_eq!(glyph.note, Some("I äm a note.".into()));
     }
 
+    #[test]
+    fn number_grammar() {
+        for valid in ["0", "-12", "1.5", "-.5", "5.", "1e3", "1.5E-3", "2e+2"] {
+            assert!(parse_number(valid).is_ok(), "{}", valid);
+        }
+        for malformed in [
+            "", "-", ".", "+5", "inf", "-inf", "NaN", "1e", "1e+", "0x10", " 1",
+        ] {
+            assert!(
+                matches!(
+                    parse_number(malformed),
+                    Err(Error::Parse(ErrorKind::MalformedNumber(_)))
+                ),
+                "{}",
+                malformed
+            );
+        }
+        assert!(matches!(
+            parse_number("1e999"),
+            Err(Error::Parse(ErrorKind::NumberOutOfRange(_)))
+        ));
+    }
+
+    #[test]
+    fn codepoint_grammar() {
+        assert_eq!(parse_codepoint("0041").unwrap(), 'A');
+        assert_eq!(parse_codepoint("10FFFF").unwrap(), '\u{10FFFF}');
+        for malformed in ["", "+41", "-41", "0x41", "4 1", "G"] {
+            assert!(
+                matches!(
+                    parse_codepoint(malformed),
+                    Err(Error::Parse(ErrorKind::MalformedHex(_)))
+                ),
+                "{}",
+                malformed
+            );
+        }
+        for out_of_range in ["110000", "FFFFFFFFF"] {
+            assert!(matches!(
+                parse_codepoint(out_of_range),
+                Err(Error::Parse(ErrorKind::CodepointOutOfRange(_)))
+            ));
+        }
+        assert!(matches!(
+            parse_codepoint("D800"),
+            Err(Error::Parse(ErrorKind::InvalidCodepoint(_, _)))
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "MalformedNumber")]
+    fn nan_coordinate() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="A" format="2">
+            <anchor name="top" x="NaN" y="0"/>
+        </glyph>
+        "#;
+
+        let _ = parse_glif(xml.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn escaped_attributes() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="A&amp;B" format="2">
+            <unicode hex="&#48;041"/>
+            <anchor name="&lt;top&gt;" x="&#49;0" y="-&#x32;.5"/>
+        </glyph>
+        "#;
+
+        let glyph = parse_glif(xml.as_bytes()).unwrap();
+        assert_eq!(&*glyph.name, "A&B");
+        assert_eq!(glyph.codepoints, vec!['A']);
+        assert_eq!(glyph.anchors[0].name.as_deref(), Some("<top>"));
+        assert_eq!((glyph.anchors[0].x, glyph.anchors[0].y), (10.0, -2.5));
+    }
+
+    #[test]
+    fn encoding_byte_order_mark() {
+        let xml = "\u{FEFF}<?xml version=\"1.0\" encoding=\"utf-8\"?>
+        <glyph name=\"A\" format=\"2\"><note>ä</note></glyph>
+        ";
+
+        let glyph = parse_glif(xml.as_bytes()).unwrap();
+        assert_eq!(glyph.note, Some("ä".into()));
+    }
+
+    #[test]
+    fn encoding_rejected() {
+        let utf16 = r#"<?xml version="1.0" encoding="UTF-16"?><glyph name="A" format="2"/>"#;
+        assert!(matches!(
+            parse_glif(utf16.as_bytes()),
+            Err(Error::Parse(ErrorKind::UnsupportedEncoding(e))) if e == "UTF-16"
+        ));
+
+        let utf16_bom = [0xFF, 0xFE, b'<', 0];
+        assert!(matches!(
+            parse_glif(&utf16_bom),
+            Err(Error::Parse(ErrorKind::UnsupportedEncoding(_)))
+        ));
+
+        let latin1 = b"<?xml version=\"1.0\"?><glyph name=\"\xC4\" format=\"2\"/>";
+        assert!(matches!(
+            parse_glif(latin1),
+            Err(Error::Parse(ErrorKind::InvalidUtf8(34)))
+        ));
+
+        let xml11 = r#"<?xml version="1.1"?><glyph name="A" format="2"/>"#;
+        assert!(matches!(
+            parse_glif(xml11.as_bytes()),
+            Err(Error::Parse(ErrorKind::UnsupportedXmlVersion(_)))
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "DocTypeNotAllowed")]
+    fn doctype() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <!DOCTYPE glyph [<!ENTITY lol "lol">]>
+        <glyph name="A" format="2"><note>&lol;</note></glyph>
+        "#;
+
+        let _ = parse_glif(xml.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn processing_instructions() {
+        for xml in [
+            r#"<?xml-stylesheet href="a.xsl"?><glyph name="A" format="2"/>"#,
+            r#"<glyph name="A" format="2"><?php ?></glyph>"#,
+            r#"<glyph name="A" format="2"><note><?php ?></note></glyph>"#,
+            r#"<glyph name="A" format="2"><lib><dict><?php ?></dict></lib></glyph>"#,
+        ] {
+            assert!(
+                matches!(
+                    parse_glif(xml.as_bytes()),
+                    Err(Error::Parse(ErrorKind::ProcessingInstructionNotAllowed))
+                ),
+                "{}",
+                xml
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_entities() {
+        for xml in [
+            r#"<glyph name="&ouml;" format="2"></glyph>"#,
+            r#"<glyph name="A" format="2"><note>&xxe;</note></glyph>"#,
+            r#"<glyph name="A" format="2"><lib><dict><key>&xxe;</key><true/></dict></lib></glyph>"#,
+        ] {
+            assert!(
+                matches!(
+                    parse_glif(xml.as_bytes()),
+                    Err(Error::Parse(ErrorKind::UnknownEntity(e))) if e == "ouml" || e == "xxe"
+                ),
+                "{}",
+                xml
+            );
+        }
+    }
+
+    #[test]
+    fn unexpected_content() {
+        for (xml, element) in [
+            (r#"<glyph name="A" format="2"><foo/></glyph>"#, "foo"),
+            (r#"<glyph name="A" format="2"><foo></foo></glyph>"#, "foo"),
+            (
+                r#"<glyph name="A" format="2"><advance></advance></glyph>"#,
+                "advance",
+            ),
+            (r#"<glyph name="A" format="2"><note/><bar/></glyph>"#, "bar"),
+            (
+                r#"<glyph name="A" format="2"><outline><point x="0" y="0"/></outline></glyph>"#,
+                "point",
+            ),
+            (
+                r#"<glyph name="A" format="2"><outline><contour><point x="0" y="0"></point></contour></outline></glyph>"#,
+                "point",
+            ),
+        ] {
+            assert!(
+                matches!(
+                    parse_glif(xml.as_bytes()),
+                    Err(Error::Parse(ErrorKind::UnexpectedElement(e))) if e == element
+                ),
+                "{}",
+                xml
+            );
+        }
+
+        let xml = r#"<glyph name="A" format="2">stray</glyph>"#;
+        assert!(matches!(
+            parse_glif(xml.as_bytes()),
+            Err(Error::Parse(ErrorKind::UnexpectedText))
+        ));
+
+        let xml = r#"<glyph name="A" format="2"><?xml version="1.0"?></glyph>"#;
+        assert!(matches!(
+            parse_glif(xml.as_bytes()),
+            Err(Error::Parse(ErrorKind::MisplacedDeclaration))
+        ));
+    }
+
+    #[test]
+    fn empty_note_and_lib() {
+        let xml = r#"<glyph name="A" format="2"><note/><lib/></glyph>"#;
+        let glyph = parse_glif(xml.as_bytes()).unwrap();
+        assert_eq!(glyph.note, Some(String::new()));
+        assert!(glyph.lib.is_empty());
+
+        let xml = r#"<glyph name="A" format="2"><note/><note>A</note></glyph>"#;
+        assert!(matches!(
+            parse_glif(xml.as_bytes()),
+            Err(Error::Parse(ErrorKind::DuplicateElement))
+        ));
+    }
+
+    #[test]
+    fn limits() {
+        let limits = Limits {
+            max_input_size: 1024,
+            max_string_length: 16,
+            max_codepoints: 2,
+            max_anchors: 1,
+            max_guidelines: 1,
+            max_contours: 1,
+            max_points: 2,
+            max_components: 1,
+            max_lib_depth: 3,
+        };
+        let cases = [
+            (
+                r#"<glyph name="A" format="2"><unicode hex="41"/><unicode hex="61"/><unicode hex="C0"/></glyph>"#,
+                "codepoints",
+            ),
+            (
+                r#"<glyph name="A" format="2"><anchor x="0" y="0"/><anchor x="1" y="1"/></glyph>"#,
+                "anchors",
+            ),
+            (
+                r#"<glyph name="A" format="2"><guideline x="0"/><guideline y="1"/></glyph>"#,
+                "guidelines",
+            ),
+            (
+                r#"<glyph name="A" format="2"><outline><contour/><contour/></outline></glyph>"#,
+                "contours",
+            ),
+            (
+                r#"<glyph name="A" format="2"><outline><contour><point x="0" y="0"/><point x="1" y="1"/><point x="2" y="2"/></contour></outline></glyph>"#,
+                "points",
+            ),
+            (
+                r#"<glyph name="A" format="2"><outline><component base="B"/><component base="C"/></outline></glyph>"#,
+                "components",
+            ),
+            (
+                r#"<glyph name="AAAAAAAAAAAAAAAAA" format="2"></glyph>"#,
+                "string length",
+            ),
+            (
+                r#"<glyph name="A" format="2"><note>12345678 12345678</note></glyph>"#,
+                "string length",
+            ),
+            (
+                r#"<glyph name="A" format="2"><lib><dict><key>a</key><array><array><true/></array></array></dict></lib></glyph>"#,
+                "lib nesting depth",
+            ),
+        ];
+        let options = ParseOptions {
+            limits,
+            ..Default::default()
+        };
+        for (xml, expected) in cases {
+            match parse_glif_with_options(xml.as_bytes(), &options) {
+                Err(Error::Parse(ErrorKind::LimitExceeded { what, .. })) => {
+                    assert_eq!(what, expected)
+                }
+                other => panic!("expected {} limit error, got {:?}", expected, other),
+            }
+        }
+
+        let xml = format!(r#"<glyph name="A" format="2">{}</glyph>"#, " ".repeat(1024));
+        assert!(matches!(
+            parse_glif_with_options(xml.as_bytes(), &options),
+            Err(Error::Parse(ErrorKind::LimitExceeded {
+                what: "input size",
+                limit: 1024
+            }))
+        ));
+    }
+
+    #[test]
+    fn skip_elements() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="A" format="2">
+            <unicode hex="0041"/>
+            <advance width="500"/>
+            <image fileName="sketch.png"/>
+            <anchor name="top" x="250" y="700"/>
+            <guideline x="250"/>
+            <outline>
+                <component base="B"/>
+            </outline>
+            <lib>
+                <dict>
+                    <key>com.example.data</key>
+                    <array><string>x</string></array>
+                </dict>
+            </lib>
+            <note>A note.</note>
+        </glyph>
+        "#;
+        let options = ParseOptions {
+            skip_lib: true,
+            skip_note: true,
+            skip_image: true,
+            skip_anchors_and_guidelines: true,
+            skip_outline: true,
+            ..Default::default()
+        };
+
+        let (glyph, _) = parse_glif_with_options(xml.as_bytes(), &options).unwrap();
+        assert_eq!(glyph.codepoints, vec!['A']);
+        assert_eq!(glyph.width, 500.0);
+        assert!(glyph.lib.is_empty());
+        assert_eq!(glyph.note, None);
+        assert_eq!(glyph.image, None);
+        assert!(glyph.anchors.is_empty());
+        assert!(glyph.guidelines.is_empty());
+
+        let duplicate_lib =
+            r#"<glyph name="A" format="2"><lib><dict/></lib><lib><dict/></lib></glyph>"#;
+        assert!(matches!(
+            parse_glif_with_options(duplicate_lib.as_bytes(), &options),
+            Err(Error::Parse(ErrorKind::DuplicateElement))
+        ));
+
+        let empty_note = r#"<glyph name="A" format="2"><note/></glyph>"#;
+        let (glyph, _) = parse_glif_with_options(empty_note.as_bytes(), &options).unwrap();
+        assert_eq!(glyph.note, None);
+        let duplicate_note = r#"<glyph name="A" format="2"><note>A</note><note/></glyph>"#;
+        assert!(matches!(
+            parse_glif_with_options(duplicate_note.as_bytes(), &options),
+            Err(Error::Parse(ErrorKind::DuplicateElement))
+        ));
+    }
+
+    #[test]
+    fn glif_header() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="Adieresis" format="2" formatMinor="1">
+            <unicode hex="00C4"/>
+            <outline>
+                <component base="A"/>
+                <component base="dieresiscomb" xOffset="120"/>
+            </outline>
+            <lib>
+                <dict>
+                    <key>unicode</key>
+                    <string>not a codepoint</string>
+                </dict>
+            </lib>
+            <unicode hex="0041"/>
+        </glyph>
+        "#;
+
+        let header = read_glif_header(xml.as_bytes()).unwrap();
+        assert_eq!(
+            header,
+            GlifHeader {
+                name: "Adieresis".into(),
+                format: GlifVersion::V2,
+                format_minor: 1,
+                codepoints: vec!['\u{00C4}', 'A'],
+            }
+        );
+    }
+
+    #[test]
+    fn parse_outline() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="A" format="2">
+            <outline>
+                <contour identifier="c0">
+                    <point x="0" y="0" type="line"/>
+                    <point x="0" y="100" type="line" name="top" smooth="no"/>
+                    <point x="100" y="100"/>
+                    <point x="100" y="0" type="qcurve" smooth="yes" identifier="p3"/>
+                </contour>
+                <contour/>
+                <component base="B" xOffset="10" identifier="c1"/>
+            </outline>
+        </glyph>
+        "#;
+
+        let glyph = parse_glif(xml.as_bytes()).unwrap();
+        assert_eq!(
+            glyph.contours,
+            vec![Contour::new(
+                vec![
+                    ContourPoint::new(0.0, 0.0, PointType::Line, false, None, None, None),
+                    ContourPoint::new(
+                        0.0,
+                        100.0,
+                        PointType::Line,
+                        false,
+                        Some("top".into()),
+                        None,
+                        None
+                    ),
+                    ContourPoint::new(100.0, 100.0, PointType::OffCurve, false, None, None, None),
+                    ContourPoint::new(
+                        100.0,
+                        0.0,
+                        PointType::QCurve,
+                        true,
+                        None,
+                        Some(Identifier::new("p3").unwrap()),
+                        None
+                    ),
+                ],
+                Some(Identifier::new("c0").unwrap()),
+                None
+            )]
+        );
+        assert_eq!(
+            glyph.components,
+            vec![Component::new(
+                "B".into(),
+                AffineTransform {
+                    x_offset: 10.0,
+                    ..Default::default()
+                },
+                Some(Identifier::new("c1").unwrap()),
+                None
+            )]
+        );
+
+        let xml = r#"<glyph name="A" format="2"><outline/><outline/></glyph>"#;
+        assert!(matches!(
+            parse_glif(xml.as_bytes()),
+            Err(Error::Parse(ErrorKind::DuplicateElement))
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "kind: TrailingOffCurves")]
+    fn outline_bad_point_sequence() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="A" format="2">
+            <outline>
+                <contour>
+                    <point x="0" y="0" type="move"/>
+                    <point x="0" y="100"/>
+                </contour>
+            </outline>
+        </glyph>
+        "#;
+
+        let _ = parse_glif(xml.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn outline_error_location() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="A" format="2">
+            <outline>
+                <contour>
+                    <point x="0" y="0" type="line"/>
+                </contour>
+                <contour/>
+                <contour>
+                    <point x="0" y="0" type="line"/>
+                    <point x="10" y="20"/>
+                    <point x="30" y="40"/>
+                    <point x="50" y="60"/>
+                    <point x="70" y="80" type="curve"/>
+                </contour>
+            </outline>
+        </glyph>
+        "#;
+
+        let error = parse_glif(xml.as_bytes()).unwrap_err();
+        match &error {
+            Error::Parse(ErrorKind::Outline(e)) => assert_eq!(
+                e,
+                &OutlineError {
+                    kind: norad::error::ErrorKind::TooManyOffCurves,
+                    contour: Some(2),
+                    point: Some(4),
+                    position: Some((70.0, 80.0)),
+                }
+            ),
+            e => panic!("unexpected error {:?}", e),
+        }
+        assert!(error
+            .to_string()
+            .ends_with(" at contour 2, point 4 (70, 80)"));
+    }
+
+    #[test]
+    fn visitor() {
+        #[derive(Default)]
+        struct Statistics<'a> {
+            name: Cow<'a, str>,
+            contours: usize,
+            points: usize,
+            components: Vec<Cow<'a, str>>,
+            lib_keys: usize,
+        }
+
+        impl<'a> GlifVisitor<'a> for Statistics<'a> {
+            fn glyph(&mut self, name: Cow<'a, str>, _: GlifVersion, _: u32) -> Result<(), Error> {
+                self.name = name;
+                Ok(())
+            }
+
+            fn begin_contour(&mut self, _: Option<Identifier>) -> Result<(), Error> {
+                self.contours += 1;
+                Ok(())
+            }
+
+            fn point(&mut self, _: PointRef<'a>) -> Result<(), Error> {
+                self.points += 1;
+                Ok(())
+            }
+
+            fn component(&mut self, component: ComponentRef<'a>) -> Result<(), Error> {
+                self.components.push(component.base);
+                Ok(())
+            }
+
+            fn lib(&mut self, lib: RawLib<'a>) -> Result<(), Error> {
+                self.lib_keys = lib.to_dictionary_ref()?.len();
+                Ok(())
+            }
+        }
+
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="Aogonek" format="2">
+            <outline>
+                <contour>
+                    <point x="0" y="0" type="move"/>
+                    <point x="0" y="100"/>
+                    <point x="100" y="100" type="qcurve"/>
+                </contour>
+                <contour>
+                    <point x="0" y="0" type="line"/>
+                    <point x="10" y="10" type="line"/>
+                </contour>
+                <component base="A"/>
+                <component base="ogonekcomb"/>
+            </outline>
+            <lib>
+                <dict>
+                    <key>public.markColor</key>
+                    <string>1,0,0,1</string>
+                </dict>
+            </lib>
+        </glyph>
+        "#;
+
+        let mut statistics = Statistics::default();
+        visit_glif(xml.as_bytes(), &ParseOptions::default(), &mut statistics).unwrap();
+        assert!(matches!(statistics.name, Cow::Borrowed("Aogonek")));
+        assert_eq!(statistics.contours, 2);
+        assert_eq!(statistics.points, 5);
+        assert_eq!(statistics.components, vec!["A", "ogonekcomb"]);
+        assert_eq!(statistics.lib_keys, 1);
+    }
+
     #[test]
     #[should_panic(expected = "WrongFirstElement")]
     fn wrong_first_element() {
@@ -711,6 +2251,163 @@ mod tests {
         let _ = parse_glif(xml.as_bytes()).unwrap();
     }
 
+    #[test]
+    fn note_whitespace() {
+        let xml = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+        <glyph name=\"A\" format=\"2\">
+            <note>
+  Indented line,
+\ttabbed &amp; &#x263A; <!-- hidden --><![CDATA[<raw> & ]]>
+</note>
+        </glyph>
+        ";
+
+        let glyph = parse_glif(xml.as_bytes()).unwrap();
+        assert_eq!(
+            glyph.note,
+            Some("\n  Indented line,\n\ttabbed & \u{263A} <raw> & \n".into())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "InvalidNote")]
+    fn note_with_element() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="A" format="2">
+            <note>a <b>bold</b> note</note>
+        </glyph>
+        "#;
+
+        let _ = parse_glif(xml.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn image_file_name() {
+        for invalid in [
+            "",
+            ".",
+            "..",
+            "../sketch.png",
+            "/tmp/sketch.png",
+            "a\\b.png",
+        ] {
+            let xml = format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+                <glyph name="A" format="2">
+                    <image fileName="{}"/>
+                </glyph>
+                "#,
+                invalid
+            );
+            assert!(
+                matches!(
+                    parse_glif(xml.as_bytes()),
+                    Err(Error::Parse(ErrorKind::InvalidImageFileName(_)))
+                ),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn image_file_check() {
+        let ufo_root = std::env::temp_dir().join(format!("ufoglifparser-{}", std::process::id()));
+        std::fs::create_dir_all(ufo_root.join("images")).unwrap();
+        std::fs::write(
+            ufo_root.join("images/sketch.png"),
+            b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR",
+        )
+        .unwrap();
+        std::fs::write(ufo_root.join("images/sketch.jpg"), b"\xFF\xD8\xFF\xE0").unwrap();
+        let image = |file_name: &str| Image {
+            file_name: file_name.into(),
+            color: None,
+            transform: AffineTransform::default(),
+        };
+
+        let png = check_image_file(&image("sketch.png"), &ufo_root);
+        let jpg = check_image_file(&image("sketch.jpg"), &ufo_root);
+        let missing = check_image_file(&image("missing.png"), &ufo_root);
+        let traversal = check_image_file(&image("../sketch.png"), &ufo_root);
+        std::fs::remove_dir_all(&ufo_root).unwrap();
+
+        assert!(png.is_ok());
+        assert!(matches!(jpg, Err(Error::Parse(ErrorKind::ImageNotPng(_)))));
+        assert!(matches!(
+            missing,
+            Err(Error::Parse(ErrorKind::ImageFileMissing(_)))
+        ));
+        assert!(matches!(
+            traversal,
+            Err(Error::Parse(ErrorKind::InvalidImageFileName(_)))
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "DuplicateCodepoint")]
+    fn duplicate_codepoint() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="A" format="2">
+            <unicode hex="0041"/>
+            <unicode hex="0041"/>
+        </glyph>
+        "#;
+
+        let _ = parse_glif(xml.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn duplicate_codepoint_lenient() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="A" format="2">
+            <unicode hex="0041"/>
+            <unicode hex="0061"/>
+            <unicode hex="0041"/>
+        </glyph>
+        "#;
+
+        let (glyph, warnings) = parse_glif_lenient(xml.as_bytes()).unwrap();
+        assert_eq!(glyph.codepoints, vec!['A', 'a']);
+        assert_eq!(warnings, vec![Warning::DuplicateCodepoint('A')]);
+    }
+
+    #[test]
+    fn empty_contours() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <glyph name="A" format="2">
+            <outline>
+                <contour identifier="empty1"/>
+                <contour>
+                    <point x="0" y="0" type="line"/>
+                </contour>
+                <contour></contour>
+            </outline>
+        </glyph>
+        "#;
+
+        let (glyph, warnings) =
+            parse_glif_with_options(xml.as_bytes(), &ParseOptions::default()).unwrap();
+        assert_eq!(glyph.contours.len(), 1);
+        assert_eq!(
+            warnings,
+            vec![
+                Warning::EmptyContour {
+                    contour: 0,
+                    identifier: Some(Identifier::new("empty1").unwrap()),
+                },
+                Warning::EmptyContour {
+                    contour: 2,
+                    identifier: None,
+                },
+            ]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "dropped empty contour 0 with identifier 'empty1'"
+        );
+    }
+
     #[test]
     #[should_panic(expected = "DuplicateElement")]
     fn duplicate_lib() {
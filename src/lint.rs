@@ -0,0 +1,549 @@
+//! Quality checks for glyph outlines.
+//!
+//! [`lint_glyph`] runs the [`Rule`]s chosen in [`LintOptions`] on the contours of a
+//! glyph and reports each problem as a [`Finding`]. Components are not followed;
+//! lint their base glyphs instead.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use norad::{Contour, ContourPoint, Glyph, PointType};
+
+use crate::geometry::{segments, Segment};
+
+/// A check run by [`lint_glyph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rule {
+    /// A segment whose start, end and off-curve points all coincide. Zero-length lines
+    /// are also reported as [`Rule::DuplicatePoint`].
+    ZeroLengthSegment,
+    /// Two consecutive on-curve points, or two consecutive off-curve points, that
+    /// coincide.
+    DuplicatePoint,
+    /// A contour with a single point.
+    StrayPoint,
+    /// A smooth point where the outline changes direction.
+    SmoothNotCollinear,
+    /// An off-curve point on top of the on-curve point it is attached to.
+    HandleOnPoint,
+    /// An open contour.
+    OpenContour,
+    /// A closed contour running in the same direction as the contour it is inside of,
+    /// or in a different direction than the first outermost contour.
+    InconsistentWinding,
+}
+
+impl Rule {
+    /// All rules, in the order findings for the same point are reported.
+    pub const ALL: [Rule; 7] = [
+        Rule::ZeroLengthSegment,
+        Rule::DuplicatePoint,
+        Rule::StrayPoint,
+        Rule::SmoothNotCollinear,
+        Rule::HandleOnPoint,
+        Rule::OpenContour,
+        Rule::InconsistentWinding,
+    ];
+
+    /// A stable identifier for configuration files and reports, e.g.
+    /// `"zero-length-segment"`.
+    pub fn id(self) -> &'static str {
+        match self {
+            Rule::ZeroLengthSegment => "zero-length-segment",
+            Rule::DuplicatePoint => "duplicate-point",
+            Rule::StrayPoint => "stray-point",
+            Rule::SmoothNotCollinear => "smooth-not-collinear",
+            Rule::HandleOnPoint => "handle-on-point",
+            Rule::OpenContour => "open-contour",
+            Rule::InconsistentWinding => "inconsistent-winding",
+        }
+    }
+
+    /// The rule with the given [identifier](Rule::id).
+    pub fn from_id(id: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.id() == id)
+    }
+
+    /// The severity of the rule's findings unless configured otherwise.
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Rule::HandleOnPoint => Severity::Info,
+            Rule::ZeroLengthSegment
+            | Rule::DuplicatePoint
+            | Rule::StrayPoint
+            | Rule::SmoothNotCollinear => Severity::Warning,
+            Rule::OpenContour | Rule::InconsistentWinding => Severity::Error,
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Rule::ZeroLengthSegment => "zero-length segment",
+            Rule::DuplicatePoint => "duplicate point",
+            Rule::StrayPoint => "stray point",
+            Rule::SmoothNotCollinear => "smooth point with a corner",
+            Rule::HandleOnPoint => "handle on its on-curve point",
+            Rule::OpenContour => "open contour",
+            Rule::InconsistentWinding => "contour with inconsistent winding",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// How serious a [`Finding`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// Probably harmless, but worth a look.
+    Info,
+    /// Likely to cause problems, e.g. in hinting or overlap removal.
+    Warning,
+    /// Will render incorrectly.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// A problem found by [`lint_glyph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub rule: Rule,
+    pub severity: Severity,
+    /// The index of the contour in the glyph.
+    pub contour: usize,
+    /// The index of the point in the contour, for rules about a single point.
+    pub point: Option<usize>,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {} at contour {}",
+            self.severity,
+            self.rule,
+            self.rule.description(),
+            self.contour
+        )?;
+        if let Some(point) = self.point {
+            write!(f, ", point {}", point)?;
+        }
+        Ok(())
+    }
+}
+
+/// Which rules [`lint_glyph`] runs, and how strictly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintOptions {
+    /// The rules to run, with the severity to report their findings at. All rules at
+    /// their [default severity](Rule::default_severity) by default.
+    pub rules: BTreeMap<Rule, Severity>,
+    /// The distance up to which points count as coinciding.
+    pub tolerance: f64,
+    /// The angle in degrees up to which the outline may turn at a smooth point.
+    pub smooth_angle_tolerance: f64,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        LintOptions {
+            rules: Rule::ALL
+                .into_iter()
+                .map(|rule| (rule, rule.default_severity()))
+                .collect(),
+            tolerance: 0.0,
+            smooth_angle_tolerance: 1.0,
+        }
+    }
+}
+
+/// Run the configured rules on the contours of `glyph`.
+///
+/// Findings are sorted by contour and point, with contour-level findings first.
+pub fn lint_glyph(glyph: &Glyph, options: &LintOptions) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for (index, contour) in glyph.contours.iter().enumerate() {
+        ContourLinter {
+            contour: index,
+            points: &contour.points,
+            closed: contour.points.first().map(|p| p.typ) != Some(PointType::Move),
+            options,
+            findings: &mut findings,
+        }
+        .run();
+    }
+    if let Some(&severity) = options.rules.get(&Rule::InconsistentWinding) {
+        for contour in inconsistent_winding(&glyph.contours) {
+            findings.push(Finding {
+                rule: Rule::InconsistentWinding,
+                severity,
+                contour,
+                point: None,
+            });
+        }
+    }
+    findings.sort_by_key(|f| (f.contour, f.point, f.rule));
+    findings
+}
+
+/// The rules concerning a single contour.
+struct ContourLinter<'a> {
+    contour: usize,
+    points: &'a [ContourPoint],
+    closed: bool,
+    options: &'a LintOptions,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl ContourLinter<'_> {
+    fn run(mut self) {
+        if self.points.len() == 1 {
+            self.report(Rule::StrayPoint, None);
+            return;
+        }
+        if !self.closed {
+            self.report(Rule::OpenContour, None);
+        }
+        for index in 0..self.points.len() {
+            let point = &self.points[index];
+            let previous = self.previous(index).map(|i| &self.points[i]);
+            let next = self.next(index).map(|i| &self.points[i]);
+            let on_curve = point.typ != PointType::OffCurve;
+
+            if let Some(previous) = previous {
+                let previous_on_curve = previous.typ != PointType::OffCurve;
+                if on_curve == previous_on_curve && self.coincide(point, previous) {
+                    self.report(Rule::DuplicatePoint, Some(index));
+                }
+            }
+            if on_curve {
+                if self.is_zero_length_segment(index) {
+                    self.report(Rule::ZeroLengthSegment, Some(index));
+                }
+                if point.smooth {
+                    if let (Some(previous), Some(next)) = (previous, next) {
+                        if self.turns(previous, point, next) {
+                            self.report(Rule::SmoothNotCollinear, Some(index));
+                        }
+                    }
+                }
+            } else {
+                let on_its_point = [previous, next].into_iter().flatten().any(|neighbour| {
+                    neighbour.typ != PointType::OffCurve && self.coincide(point, neighbour)
+                });
+                if on_its_point {
+                    self.report(Rule::HandleOnPoint, Some(index));
+                }
+            }
+        }
+    }
+
+    fn report(&mut self, rule: Rule, point: Option<usize>) {
+        if let Some(&severity) = self.options.rules.get(&rule) {
+            self.findings.push(Finding {
+                rule,
+                severity,
+                contour: self.contour,
+                point,
+            });
+        }
+    }
+
+    fn previous(&self, index: usize) -> Option<usize> {
+        match index {
+            0 if self.closed => Some(self.points.len() - 1),
+            0 => None,
+            _ => Some(index - 1),
+        }
+    }
+
+    fn next(&self, index: usize) -> Option<usize> {
+        if index + 1 < self.points.len() {
+            Some(index + 1)
+        } else if self.closed {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    fn coincide(&self, a: &ContourPoint, b: &ContourPoint) -> bool {
+        (a.x - b.x).hypot(a.y - b.y) <= self.options.tolerance
+    }
+
+    /// Whether the segment ending at the on-curve point `index` has all its points on
+    /// top of each other.
+    fn is_zero_length_segment(&self, index: usize) -> bool {
+        let end = &self.points[index];
+        let mut current = index;
+        while let Some(previous) = self.previous(current) {
+            if previous == index {
+                // Only off-curve points besides this one.
+                return false;
+            }
+            let point = &self.points[previous];
+            if !self.coincide(point, end) {
+                return false;
+            }
+            if point.typ != PointType::OffCurve {
+                return true;
+            }
+            current = previous;
+        }
+        false
+    }
+
+    /// Whether the outline turns by more than the tolerance at `point`.
+    fn turns(&self, previous: &ContourPoint, point: &ContourPoint, next: &ContourPoint) -> bool {
+        let incoming = (point.x - previous.x, point.y - previous.y);
+        let outgoing = (next.x - point.x, next.y - point.y);
+        if incoming == (0.0, 0.0) || outgoing == (0.0, 0.0) {
+            // Handles on their point are reported by their own rule.
+            return false;
+        }
+        let cross = incoming.0 * outgoing.1 - incoming.1 * outgoing.0;
+        let dot = incoming.0 * outgoing.0 + incoming.1 * outgoing.1;
+        cross.abs().atan2(dot).to_degrees() > self.options.smooth_angle_tolerance
+    }
+}
+
+/// The number of straight pieces each curve is split into to compute areas and
+/// containment.
+const FLATTENING_STEPS: usize = 16;
+
+/// How close to a polygon's edge a point has to be to count as on it.
+const EDGE_TOLERANCE: f64 = 1e-9;
+
+/// A closed contour flattened for the winding rule.
+struct Polygon {
+    contour: usize,
+    points: Vec<(f64, f64)>,
+    /// Positive if the contour runs counter-clockwise.
+    area: f64,
+}
+
+/// The indices of closed contours whose direction doesn't alternate with their
+/// nesting depth, taking the first outermost contour's direction as the reference.
+fn inconsistent_winding(contours: &[Contour]) -> Vec<usize> {
+    let polygons: Vec<Polygon> = contours
+        .iter()
+        .enumerate()
+        .filter(|(_, contour)| contour.points.first().map(|p| p.typ) != Some(PointType::Move))
+        .map(|(index, contour)| {
+            let points = flatten(contour);
+            Polygon {
+                contour: index,
+                area: signed_area(&points),
+                points,
+            }
+        })
+        .filter(|polygon| polygon.area != 0.0)
+        .collect();
+
+    let depths: Vec<usize> = polygons
+        .iter()
+        .map(|polygon| {
+            polygons
+                .iter()
+                .filter(|other| other.contour != polygon.contour)
+                .filter(|other| is_inside(polygon, other))
+                .count()
+        })
+        .collect();
+    let counter_clockwise = match depths.iter().position(|&depth| depth == 0) {
+        Some(outermost) => polygons[outermost].area > 0.0,
+        None => return Vec::new(),
+    };
+    polygons
+        .iter()
+        .zip(depths)
+        .filter(|(polygon, depth)| (polygon.area > 0.0) != (counter_clockwise == (depth % 2 == 0)))
+        .map(|(polygon, _)| polygon.contour)
+        .collect()
+}
+
+/// The contour as a polygon, with curves approximated by straight pieces.
+fn flatten(contour: &Contour) -> Vec<(f64, f64)> {
+    let mut polygon = Vec::new();
+    for segment in segments(contour) {
+        let (start, _) = segment.ends();
+        polygon.push(start);
+        if !matches!(segment, Segment::Line(_)) {
+            for step in 1..FLATTENING_STEPS {
+                polygon.push(segment.point_at(step as f64 / FLATTENING_STEPS as f64));
+            }
+        }
+    }
+    polygon
+}
+
+/// The area of a polygon, positive if it runs counter-clockwise.
+fn signed_area(polygon: &[(f64, f64)]) -> f64 {
+    let mut area = 0.0;
+    for (i, &(x0, y0)) in polygon.iter().enumerate() {
+        let (x1, y1) = polygon[(i + 1) % polygon.len()];
+        area += x0 * y1 - x1 * y0;
+    }
+    area / 2.0
+}
+
+/// Whether `inner` is inside `outer`, judged by the first point of `inner` that is not
+/// on the edge of `outer`, so that a contour touching its container counts as inside.
+fn is_inside(inner: &Polygon, outer: &Polygon) -> bool {
+    inner
+        .points
+        .iter()
+        .find(|&&point| !on_edge(&outer.points, point))
+        .is_some_and(|&point| contains(&outer.points, point))
+}
+
+/// Whether `point` is on the edge of `polygon`.
+fn on_edge(polygon: &[(f64, f64)], (x, y): (f64, f64)) -> bool {
+    polygon.iter().enumerate().any(|(i, &(x0, y0))| {
+        let (x1, y1) = polygon[(i + 1) % polygon.len()];
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let length_squared = dx * dx + dy * dy;
+        let t = if length_squared == 0.0 {
+            0.0
+        } else {
+            (((x - x0) * dx + (y - y0) * dy) / length_squared).clamp(0.0, 1.0)
+        };
+        (x - (x0 + t * dx)).hypot(y - (y0 + t * dy)) <= EDGE_TOLERANCE
+    })
+}
+
+/// Whether `point` is inside `polygon`, by the even-odd rule.
+fn contains(polygon: &[(f64, f64)], (x, y): (f64, f64)) -> bool {
+    let mut inside = false;
+    for (i, &(x0, y0)) in polygon.iter().enumerate() {
+        let (x1, y1) = polygon[(i + 1) % polygon.len()];
+        if (y0 > y) != (y1 > y) && x < x0 + (y - y0) / (y1 - y0) * (x1 - x0) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test_util::contour;
+
+    fn square(x: f64, y: f64, size: f64, clockwise: bool) -> Contour {
+        let mut points = vec![
+            (x, y, PointType::Line),
+            (x + size, y, PointType::Line),
+            (x + size, y + size, PointType::Line),
+            (x, y + size, PointType::Line),
+        ];
+        if clockwise {
+            points.reverse();
+        }
+        contour(&points)
+    }
+
+    fn found(glyph: &Glyph) -> Vec<(Rule, usize, Option<usize>)> {
+        lint_glyph(glyph, &LintOptions::default())
+            .into_iter()
+            .map(|f| (f.rule, f.contour, f.point))
+            .collect()
+    }
+
+    #[test]
+    fn point_rules() {
+        use PointType::*;
+
+        let mut glyph = Glyph::new_named("a");
+        glyph.contours = vec![
+            contour(&[
+                (0.0, 0.0, Line),
+                (0.0, 0.0, Line),
+                (100.0, 0.0, Line),
+                (100.0, 0.0, OffCurve),
+                (50.0, 50.0, OffCurve),
+                (0.0, 100.0, Curve),
+                (0.0, 100.0, OffCurve),
+                (0.0, 100.0, OffCurve),
+                (0.0, 100.0, Curve),
+            ]),
+            contour(&[(10.0, 10.0, Move)]),
+            contour(&[(0.0, 0.0, Move), (50.0, 0.0, Line), (50.0, 50.0, Line)]),
+        ];
+        glyph.contours[0].points[5].smooth = true;
+        glyph.contours[2].points[1].smooth = true;
+        assert_eq!(
+            found(&glyph),
+            vec![
+                (Rule::ZeroLengthSegment, 0, Some(1)),
+                (Rule::DuplicatePoint, 0, Some(1)),
+                (Rule::HandleOnPoint, 0, Some(3)),
+                (Rule::HandleOnPoint, 0, Some(6)),
+                (Rule::DuplicatePoint, 0, Some(7)),
+                (Rule::HandleOnPoint, 0, Some(7)),
+                (Rule::ZeroLengthSegment, 0, Some(8)),
+                (Rule::StrayPoint, 1, None),
+                (Rule::OpenContour, 2, None),
+                (Rule::SmoothNotCollinear, 2, Some(1)),
+            ]
+        );
+
+        let mut options = LintOptions::default();
+        options.rules.remove(&Rule::HandleOnPoint);
+        options.rules.insert(Rule::StrayPoint, Severity::Error);
+        let findings = lint_glyph(&glyph, &options);
+        assert!(findings.iter().all(|f| f.rule != Rule::HandleOnPoint));
+        assert_eq!(
+            findings[4].to_string(),
+            "error[stray-point]: stray point at contour 1"
+        );
+        assert_eq!(Rule::from_id("handle-on-point"), Some(Rule::HandleOnPoint));
+    }
+
+    #[test]
+    fn winding() {
+        let mut glyph = Glyph::new_named("o");
+        glyph.contours = vec![
+            square(0.0, 0.0, 100.0, false),
+            square(10.0, 10.0, 80.0, true),
+            square(20.0, 20.0, 10.0, true),
+            square(200.0, 0.0, 100.0, true),
+        ];
+        assert_eq!(
+            found(&glyph),
+            vec![
+                (Rule::InconsistentWinding, 2, None),
+                (Rule::InconsistentWinding, 3, None),
+            ]
+        );
+
+        glyph.contours[2] = square(20.0, 20.0, 10.0, false);
+        glyph.contours[3] = square(200.0, 0.0, 100.0, false);
+        assert_eq!(found(&glyph), Vec::new());
+
+        // A counter touching the edge of its container, starting on it.
+        glyph.contours = vec![
+            square(0.0, 0.0, 100.0, false),
+            contour(&[
+                (100.0, 10.0, PointType::Line),
+                (50.0, 10.0, PointType::Line),
+                (50.0, 60.0, PointType::Line),
+                (100.0, 60.0, PointType::Line),
+            ]),
+        ];
+        assert_eq!(found(&glyph), Vec::new());
+    }
+}
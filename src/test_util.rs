@@ -0,0 +1,21 @@
+//! Fixtures shared by the unit tests.
+
+use std::collections::HashMap;
+
+use norad::{Contour, ContourPoint, Glyph, GlyphName, PointType};
+
+/// A point that is not smooth and has no name, identifier or lib.
+pub(crate) fn point(x: f64, y: f64, typ: PointType) -> ContourPoint {
+    ContourPoint::new(x, y, typ, false, None, None, None)
+}
+
+/// A contour of [`point`]s without an identifier.
+pub(crate) fn contour(points: &[(f64, f64, PointType)]) -> Contour {
+    let points = points.iter().map(|&(x, y, typ)| point(x, y, typ)).collect();
+    Contour::new(points, None, None)
+}
+
+/// A layer of glyphs, looked up by name.
+pub(crate) fn layer(glyphs: impl IntoIterator<Item = Glyph>) -> HashMap<GlyphName, Glyph> {
+    glyphs.into_iter().map(|g| (g.name.clone(), g)).collect()
+}
@@ -0,0 +1,239 @@
+//! Applying an affine transform to a whole glyph.
+
+use norad::{AffineTransform, Glyph, Line};
+
+use crate::decompose::reverse_points;
+use crate::geometry::{apply, compose, is_mirroring};
+
+/// How close to horizontal or vertical, in degrees, a transformed guideline has to be
+/// to become a [`Line::Horizontal`] or [`Line::Vertical`].
+const AXIS_TOLERANCE: f64 = 1e-9;
+
+/// Transform everything positioned in a glyph's coordinate space by `transform`.
+///
+/// This moves contour points and anchors, applies `transform` on top of the
+/// transforms of components and the image, and moves and turns guidelines. A
+/// guideline that ends up horizontal or vertical becomes a [`Line::Horizontal`] or
+/// [`Line::Vertical`], any other one a [`Line::Angle`]. Contours are reversed by
+/// mirroring transforms, as in [`decompose`](crate::decompose).
+///
+/// A transform that is not invertible can collapse a guideline into a single point. It
+/// then keeps its orientation and goes through that point.
+///
+/// The advance is not changed.
+pub fn transform_glyph(glyph: &mut Glyph, transform: &AffineTransform) {
+    let mirroring = is_mirroring(transform);
+    for contour in &mut glyph.contours {
+        for point in &mut contour.points {
+            (point.x, point.y) = apply(transform, (point.x, point.y));
+        }
+        if mirroring {
+            contour.points = reverse_points(std::mem::take(&mut contour.points));
+        }
+    }
+    for component in &mut glyph.components {
+        component.transform = compose(transform, &component.transform);
+    }
+    for anchor in &mut glyph.anchors {
+        (anchor.x, anchor.y) = apply(transform, (anchor.x, anchor.y));
+    }
+    for guideline in &mut glyph.guidelines {
+        guideline.line = transform_line(&guideline.line, transform);
+    }
+    if let Some(image) = &mut glyph.image {
+        image.transform = compose(transform, &image.transform);
+    }
+}
+
+/// Transform a guideline's line, described by a point on it and its direction.
+fn transform_line(line: &Line, transform: &AffineTransform) -> Line {
+    let (point, direction) = match *line {
+        Line::Horizontal(y) => ((0.0, y), (1.0, 0.0)),
+        Line::Vertical(x) => ((x, 0.0), (0.0, 1.0)),
+        Line::Angle { x, y, degrees } => {
+            let radians = degrees.to_radians();
+            ((x, y), (radians.cos(), radians.sin()))
+        }
+    };
+    let (x, y) = apply(transform, point);
+    let (dx, dy) = (
+        transform.x_scale * direction.0 + transform.yx_scale * direction.1,
+        transform.xy_scale * direction.0 + transform.y_scale * direction.1,
+    );
+    // A collapsed line has no direction left; keep the original one.
+    let (dx, dy) = if (dx, dy) == (0.0, 0.0) {
+        direction
+    } else {
+        (dx, dy)
+    };
+    let degrees = dy.atan2(dx).to_degrees().rem_euclid(360.0);
+    let off_axis = degrees.rem_euclid(90.0);
+    if off_axis.min(90.0 - off_axis) <= AXIS_TOLERANCE {
+        if (degrees.rem_euclid(180.0) - 90.0).abs() <= 45.0 {
+            Line::Vertical(x)
+        } else {
+            Line::Horizontal(y)
+        }
+    } else {
+        Line::Angle { x, y, degrees }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use norad::{Anchor, Component, Guideline, Image, PointType};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test_util::contour;
+
+    fn glyph() -> Glyph {
+        let mut glyph = Glyph::new_named("a");
+        glyph.width = 500.0;
+        glyph.contours = vec![contour(&[
+            (0.0, 0.0, PointType::Line),
+            (0.0, 100.0, PointType::Line),
+            (100.0, 0.0, PointType::Line),
+        ])];
+        glyph.components = vec![Component::new(
+            "b".into(),
+            AffineTransform {
+                x_offset: 10.0,
+                ..Default::default()
+            },
+            None,
+            None,
+        )];
+        glyph.anchors = vec![Anchor::new(50.0, 200.0, None, None, None, None)];
+        glyph.image = Some(Image {
+            file_name: "sketch.png".into(),
+            color: None,
+            transform: AffineTransform {
+                x_scale: 2.0,
+                y_scale: 2.0,
+                ..Default::default()
+            },
+        });
+        glyph.guidelines = [
+            Line::Horizontal(100.0),
+            Line::Vertical(20.0),
+            Line::Angle {
+                x: 0.0,
+                y: 0.0,
+                degrees: 45.0,
+            },
+        ]
+        .into_iter()
+        .map(|line| Guideline::new(line, None, None, None, None))
+        .collect();
+        glyph
+    }
+
+    fn lines(glyph: &Glyph) -> Vec<Line> {
+        glyph.guidelines.iter().map(|g| g.line.clone()).collect()
+    }
+
+    #[test]
+    fn slant() {
+        let mut glyph = glyph();
+        let slant = AffineTransform {
+            yx_scale: 0.5,
+            ..Default::default()
+        };
+        transform_glyph(&mut glyph, &slant);
+
+        let points: Vec<_> = glyph.contours[0]
+            .points
+            .iter()
+            .map(|p| (p.x, p.y))
+            .collect();
+        assert_eq!(points, vec![(0.0, 0.0), (50.0, 100.0), (100.0, 0.0)]);
+        assert_eq!((glyph.anchors[0].x, glyph.anchors[0].y), (150.0, 200.0));
+        assert_eq!(glyph.components[0].transform.yx_scale, 0.5);
+        assert_eq!(glyph.components[0].transform.x_offset, 10.0);
+        assert_eq!(
+            glyph.image.as_ref().unwrap().transform,
+            AffineTransform {
+                x_scale: 2.0,
+                yx_scale: 1.0,
+                y_scale: 2.0,
+                ..Default::default()
+            }
+        );
+        assert_eq!(glyph.width, 500.0);
+
+        let lines = lines(&glyph);
+        assert_eq!(lines[0], Line::Horizontal(100.0));
+        match lines[1] {
+            Line::Angle { x, y, degrees } => {
+                assert_eq!((x, y), (20.0, 0.0));
+                assert!((degrees - 2.0f64.atan2(1.0).to_degrees()).abs() < 1e-9);
+            }
+            ref other => panic!("expected an angled line, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rotate_and_mirror() {
+        let mut glyph = glyph();
+        let rotate = AffineTransform {
+            x_scale: 0.0,
+            xy_scale: 1.0,
+            yx_scale: -1.0,
+            y_scale: 0.0,
+            ..Default::default()
+        };
+        transform_glyph(&mut glyph, &rotate);
+        assert_eq!(
+            lines(&glyph),
+            vec![
+                Line::Vertical(-100.0),
+                Line::Horizontal(20.0),
+                Line::Angle {
+                    x: 0.0,
+                    y: 0.0,
+                    degrees: 135.0
+                },
+            ]
+        );
+
+        let mut glyph = self::glyph();
+        let mirror = AffineTransform {
+            x_scale: -1.0,
+            ..Default::default()
+        };
+        transform_glyph(&mut glyph, &mirror);
+        let points: Vec<_> = glyph.contours[0]
+            .points
+            .iter()
+            .map(|p| (p.x, p.y))
+            .collect();
+        assert_eq!(points, vec![(0.0, 0.0), (-100.0, 0.0), (0.0, 100.0)]);
+        assert_eq!(
+            lines(&glyph)[2],
+            Line::Angle {
+                x: 0.0,
+                y: 0.0,
+                degrees: 135.0
+            }
+        );
+    }
+
+    #[test]
+    fn collapse() {
+        let mut glyph = glyph();
+        let flatten = AffineTransform {
+            y_scale: 0.0,
+            ..Default::default()
+        };
+        transform_glyph(&mut glyph, &flatten);
+        assert_eq!(
+            lines(&glyph),
+            vec![
+                Line::Horizontal(0.0),
+                Line::Vertical(20.0),
+                Line::Horizontal(0.0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! An event-based interface to the glif parser.
+//!
+//! A [`GlifVisitor`] is told about each element of a glif file as the parser reads
+//! it, so that tools can compute statistics or extract data without building a
+//! [`Glyph`]. [`crate::parse_glif`] and [`crate::parse_glif_ref`] are implemented as
+//! visitors that build one.
+//!
+//! Names and other strings are handed out borrowing from the input where possible;
+//! the lib is handed out unparsed, see [`RawLib`].
+
+use std::borrow::Cow;
+
+use norad::{GlifVersion, Glyph, Identifier};
+
+use crate::builder::{OutlineBuilder, OutlineError};
+use crate::{
+    AnchorRef, ComponentRef, Error, ErrorKind, GuidelineRef, ImageRef, PointRef, RawLib, Warning,
+};
+
+/// Callbacks for the elements of a glif file, called by [`crate::visit_glif`] in
+/// document order.
+///
+/// All methods do nothing by default, so implementors only need to override the
+/// ones they are interested in. Returning an error from a callback aborts parsing
+/// and is passed through to the caller.
+#[allow(unused_variables)]
+pub trait GlifVisitor<'a> {
+    /// The `<glyph>` element, always reported first.
+    fn glyph(
+        &mut self,
+        name: Cow<'a, str>,
+        format: GlifVersion,
+        format_minor: u32,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// The `<advance>` element.
+    fn advance(&mut self, width: f64, height: f64) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// A `<unicode>` element. Duplicates are not reported.
+    fn unicode(&mut self, codepoint: char) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// An `<anchor>` element.
+    fn anchor(&mut self, anchor: AnchorRef<'a>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// A `<guideline>` element.
+    fn guideline(&mut self, guideline: GuidelineRef<'a>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// The `<image>` element.
+    fn image(&mut self, image: ImageRef<'a>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// The start of a `<contour>` element inside the `<outline>`.
+    fn begin_contour(&mut self, identifier: Option<Identifier>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// A `<point>` element inside the current contour.
+    fn point(&mut self, point: PointRef<'a>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// The end of the current `<contour>` element.
+    fn end_contour(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// A `<component>` element inside the `<outline>`.
+    fn component(&mut self, component: ComponentRef<'a>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// The `<lib>` element.
+    fn lib(&mut self, lib: RawLib<'a>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// The `<note>` element.
+    fn note(&mut self, note: Cow<'a, str>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// The visitor behind [`crate::parse_glif`], collecting everything into a [`Glyph`].
+#[derive(Debug)]
+pub(crate) struct GlyphCollector {
+    glyph: Option<Glyph>,
+    outline: OutlineBuilder,
+}
+
+impl GlyphCollector {
+    pub(crate) fn new() -> Self {
+        GlyphCollector {
+            glyph: None,
+            outline: OutlineBuilder::new(),
+        }
+    }
+
+    fn glyph_mut(&mut self) -> &mut Glyph {
+        // The parser always reports the <glyph> element before anything else.
+        self.glyph.as_mut().expect("glyph element not visited")
+    }
+
+    /// Return the collected glyph and the outline builder's warnings.
+    ///
+    /// Errors when a contour was begun but not ended.
+    pub(crate) fn finish(self) -> Result<(Glyph, Vec<Warning>), Error> {
+        let mut glyph = self.glyph.expect("glyph element not visited");
+        let warnings = self.outline.warnings().to_vec();
+        let (contours, components) = self.outline.finish().map_err(outline_error)?;
+        glyph.contours = contours;
+        glyph.components = components;
+        // TODO: move object libs
+        Ok((glyph, warnings))
+    }
+}
+
+fn outline_error(e: OutlineError) -> Error {
+    Error::Parse(ErrorKind::Outline(e))
+}
+
+impl<'a> GlifVisitor<'a> for GlyphCollector {
+    fn glyph(
+        &mut self,
+        name: Cow<'a, str>,
+        format: GlifVersion,
+        format_minor: u32,
+    ) -> Result<(), Error> {
+        let mut glyph = Glyph::new_named(&*name);
+        glyph.format = format;
+        glyph.format_minor = format_minor;
+        self.glyph = Some(glyph);
+        Ok(())
+    }
+
+    fn advance(&mut self, width: f64, height: f64) -> Result<(), Error> {
+        let glyph = self.glyph_mut();
+        glyph.width = width;
+        glyph.height = height;
+        Ok(())
+    }
+
+    fn unicode(&mut self, codepoint: char) -> Result<(), Error> {
+        self.glyph_mut().codepoints.push(codepoint);
+        Ok(())
+    }
+
+    fn anchor(&mut self, anchor: AnchorRef<'a>) -> Result<(), Error> {
+        self.glyph_mut().anchors.push(anchor.to_owned());
+        Ok(())
+    }
+
+    fn guideline(&mut self, guideline: GuidelineRef<'a>) -> Result<(), Error> {
+        self.glyph_mut().guidelines.push(guideline.to_owned());
+        Ok(())
+    }
+
+    fn image(&mut self, image: ImageRef<'a>) -> Result<(), Error> {
+        self.glyph_mut().image = Some(image.to_owned());
+        Ok(())
+    }
+
+    fn begin_contour(&mut self, identifier: Option<Identifier>) -> Result<(), Error> {
+        self.outline.begin_path(identifier).map_err(outline_error)?;
+        Ok(())
+    }
+
+    fn point(&mut self, point: PointRef<'a>) -> Result<(), Error> {
+        self.outline
+            .add_point(
+                (point.x, point.y),
+                point.typ,
+                point.smooth,
+                point.name.map(Cow::into_owned),
+                point.identifier,
+            )
+            .map_err(outline_error)?;
+        Ok(())
+    }
+
+    fn end_contour(&mut self) -> Result<(), Error> {
+        self.outline.end_path().map_err(outline_error)?;
+        Ok(())
+    }
+
+    fn component(&mut self, component: ComponentRef<'a>) -> Result<(), Error> {
+        self.outline.add_component(
+            (&*component.base).into(),
+            component.transform,
+            component.identifier,
+        );
+        Ok(())
+    }
+
+    fn lib(&mut self, lib: RawLib<'a>) -> Result<(), Error> {
+        self.glyph_mut().lib = lib.to_plist()?;
+        Ok(())
+    }
+
+    fn note(&mut self, note: Cow<'a, str>) -> Result<(), Error> {
+        self.glyph_mut().note = Some(note.into_owned());
+        Ok(())
+    }
+}